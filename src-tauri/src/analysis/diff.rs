@@ -1,7 +1,9 @@
+use super::Tokenizer;
+
 pub struct DiffAnalyzer;
 
 impl DiffAnalyzer {
-    /// 比较两个文本的相似度 (0.0 - 1.0)
+    /// 比较两个文本的相似度 (0.0 - 1.0)，按中英文混合分词后比较
     pub fn text_similarity(text1: &str, text2: &str) -> f64 {
         if text1.is_empty() && text2.is_empty() {
             return 1.0;
@@ -10,17 +12,7 @@ impl DiffAnalyzer {
             return 0.0;
         }
 
-        let words1: std::collections::HashSet<&str> = text1.split_whitespace().collect();
-        let words2: std::collections::HashSet<&str> = text2.split_whitespace().collect();
-
-        let intersection = words1.intersection(&words2).count();
-        let union = words1.union(&words2).count();
-
-        if union == 0 {
-            return 1.0;
-        }
-
-        intersection as f64 / union as f64
+        Tokenizer::similarity(text1, text2)
     }
 
     /// 判断内容是否有显著变化
@@ -31,14 +23,15 @@ impl DiffAnalyzer {
 
     /// 提取变化的关键词
     pub fn extract_changed_keywords(prev_text: &str, curr_text: &str) -> Vec<String> {
-        let prev_words: std::collections::HashSet<&str> = prev_text.split_whitespace().collect();
-        let curr_words: std::collections::HashSet<&str> = curr_text.split_whitespace().collect();
-
-        // 新增的词
-        let added: Vec<String> = curr_words
-            .difference(&prev_words)
+        let prev_words: std::collections::HashSet<String> = Tokenizer::tokenize(prev_text).into_iter().collect();
+        let mut seen = std::collections::HashSet::new();
+
+        // 新增的词，按出现顺序
+        let added: Vec<String> = Tokenizer::tokenize(curr_text)
+            .into_iter()
+            .filter(|word| Tokenizer::is_keyword(word) && !prev_words.contains(word))
+            .filter(|word| seen.insert(word.clone()))
             .take(10)
-            .map(|s| s.to_string())
             .collect();
 
         added
@@ -1,7 +1,112 @@
+use super::Tokenizer;
+use regex::Regex;
+use std::sync::OnceLock;
+
 /// 从屏幕内容中提取关键信息
 pub struct InfoExtractor;
 
+/// 一条记录的归一化信息：应用名、当前文件、活动类型和所属项目
+#[derive(Debug, Clone, Default)]
+pub struct ActivityInfo {
+    pub app: String,
+    pub file: Option<String>,
+    pub activity_type: String,
+    /// 只由自定义分类规则给出
+    pub project: Option<String>,
+}
+
+/// 应用名别名（小写）到统一名称
+const APP_ALIASES: &[(&str, &str)] = &[
+    ("visual studio code", "Visual Studio Code"),
+    ("vs code", "Visual Studio Code"),
+    ("vscode", "Visual Studio Code"),
+    ("code", "Visual Studio Code"),
+    ("google chrome", "Google Chrome"),
+    ("chrome", "Google Chrome"),
+    ("mozilla firefox", "Mozilla Firefox"),
+    ("firefox", "Mozilla Firefox"),
+    ("microsoft edge", "Microsoft Edge"),
+    ("edge", "Microsoft Edge"),
+    ("microsoft teams", "Microsoft Teams"),
+    ("teams", "Microsoft Teams"),
+    ("windows terminal", "Windows Terminal"),
+    ("powershell", "PowerShell"),
+    ("windows powershell", "PowerShell"),
+    ("cmd", "Command Prompt"),
+    ("命令提示符", "Command Prompt"),
+    ("intellij", "IntelliJ IDEA"),
+    ("intellij idea", "IntelliJ IDEA"),
+    ("pycharm", "PyCharm"),
+    ("webstorm", "WebStorm"),
+    ("goland", "GoLand"),
+    ("clion", "CLion"),
+    ("rustrover", "RustRover"),
+    ("iterm", "iTerm2"),
+    ("iterm2", "iTerm2"),
+    ("gnome-terminal", "GNOME Terminal"),
+    ("word", "Microsoft Word"),
+    ("microsoft word", "Microsoft Word"),
+    ("微信", "WeChat"),
+    ("wechat", "WeChat"),
+    ("企业微信", "WeCom"),
+    ("wecom", "WeCom"),
+    ("飞书", "Feishu"),
+    ("feishu", "Feishu"),
+    ("lark", "Feishu"),
+    ("钉钉", "DingTalk"),
+    ("dingtalk", "DingTalk"),
+];
+
+/// 识别为文件名的扩展名
+const FILE_PATTERN: &str = r"(?i)[A-Za-z0-9_\-.]+\.(?:rs|ts|tsx|js|jsx|mjs|py|vue|go|java|kt|c|cc|cpp|h|hpp|cs|rb|php|swift|sql|sh|ps1|md|json|yaml|yml|toml|html|css|scss|txt|log|lock)(?-u:\b)";
+
 impl InfoExtractor {
+    /// 根据模型给出的应用名、摘要和画面描述，得到归一化的应用名、当前文件和活动类型
+    pub fn analyze(app: &str, summary: &str, detail: &str) -> ActivityInfo {
+        let normalized = Self::normalize_app(app);
+        let file = Self::extract_file_from_title(app)
+            .or_else(|| Self::extract_file_from_title(summary))
+            .or_else(|| Self::extract_file_from_title(detail));
+        let content = format!("{}\n{}", summary, detail);
+        let activity_type = Self::infer_activity_type(&normalized, app, &content);
+
+        ActivityInfo {
+            app: normalized,
+            file,
+            activity_type,
+            project: None,
+        }
+    }
+
+    /// 统一应用名：窗口标题取应用部分，常见别名映射为统一名称
+    pub fn normalize_app(app: &str) -> String {
+        let trimmed = app.trim();
+        if trimmed.is_empty() || trimmed.eq_ignore_ascii_case("unknown") {
+            return "Unknown".to_string();
+        }
+
+        let name = Self::extract_app_from_title(trimmed);
+        let lower = name.to_lowercase();
+        APP_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == lower)
+            .map(|(_, canonical)| canonical.to_string())
+            .unwrap_or(name)
+    }
+
+    /// 找出文本中提到的应用，返回统一名称（用于按应用过滤记录）
+    pub fn mentioned_apps(text: &str) -> Vec<String> {
+        let lower = text.to_lowercase();
+        let mut apps: Vec<String> = Vec::new();
+        // code、word、edge 这类短词在问题里多半不是指应用，不参与匹配
+        for (alias, canonical) in APP_ALIASES.iter().filter(|(alias, _)| alias.len() >= 5 || !alias.is_ascii()) {
+            if contains_word(&lower, alias) && !apps.iter().any(|a| a == canonical) {
+                apps.push(canonical.to_string());
+            }
+        }
+        apps
+    }
+
     /// 从窗口标题提取应用名称
     pub fn extract_app_from_title(title: &str) -> String {
         // 常见的应用标题模式
@@ -42,21 +147,13 @@ impl InfoExtractor {
             }
         }
 
-        // 通用格式: 尝试找到带扩展名的部分
-        for part in title.split(&[' ', '-', '|'][..]) {
-            let trimmed = part.trim();
-            if trimmed.contains('.') && trimmed.len() < 100 {
-                // 检查是否像文件名
-                let ext_patterns = [".rs", ".ts", ".js", ".py", ".vue", ".tsx", ".jsx", ".md", ".json", ".html", ".css"];
-                for ext in ext_patterns {
-                    if trimmed.ends_with(ext) {
-                        return Some(trimmed.to_string());
-                    }
-                }
-            }
-        }
-
-        None
+        // 通用格式: 找到第一个带常见扩展名的文件名（中文描述里文件名前后没有空格）
+        static FILE_RE: OnceLock<Regex> = OnceLock::new();
+        let re = FILE_RE.get_or_init(|| Regex::new(FILE_PATTERN).expect("文件名规则无效"));
+        re.find_iter(title)
+            .map(|m| m.as_str().trim_start_matches(['-', '.']))
+            .find(|name| !name.is_empty() && name.len() < 100)
+            .map(|name| name.to_string())
     }
 
     /// 推断活动类型
@@ -66,7 +163,10 @@ impl InfoExtractor {
         let content_lower = content.to_lowercase();
 
         // 编程活动
-        if app_lower.contains("code") || app_lower.contains("studio") || app_lower.contains("idea") {
+        const CODING_APPS: &[&str] = &[
+            "code", "studio", "idea", "pycharm", "webstorm", "goland", "clion", "rider", "rustrover",
+        ];
+        if CODING_APPS.iter().any(|name| app_lower.contains(name)) {
             return "coding".to_string();
         }
 
@@ -78,8 +178,18 @@ impl InfoExtractor {
             return "browsing".to_string();
         }
 
+        // 飞书文档等在线文档在通讯应用内打开，先于通讯判断
+        if (app_lower.contains("飞书") || app_lower.contains("feishu") || app_lower.contains("lark"))
+            && (title_lower.contains("文档") || title_lower.contains("docs"))
+        {
+            return "documenting".to_string();
+        }
+
         // 通讯活动
-        if app_lower.contains("teams") || app_lower.contains("slack") || app_lower.contains("discord") {
+        const CHAT_APPS: &[&str] = &[
+            "teams", "slack", "discord", "微信", "wechat", "wecom", "飞书", "feishu", "lark", "钉钉", "dingtalk",
+        ];
+        if CHAT_APPS.iter().any(|name| app_lower.contains(name)) {
             return "communicating".to_string();
         }
 
@@ -89,45 +199,33 @@ impl InfoExtractor {
         }
 
         // 终端活动
-        if app_lower.contains("terminal") || app_lower.contains("cmd") || app_lower.contains("powershell") {
+        const TERMINAL_APPS: &[&str] = &[
+            "terminal", "cmd", "command prompt", "命令提示符", "powershell", "konsole", "alacritty", "kitty", "iterm",
+            "wezterm", "xterm",
+        ];
+        if TERMINAL_APPS.iter().any(|name| app_lower.contains(name)) {
             return "terminal".to_string();
         }
 
         "general".to_string()
     }
 
-    /// 提取关键词
+    /// 提取关键词（中英文混合分词，去掉停用词后按出现次数排序）
     pub fn extract_keywords(content: &str, max_keywords: usize) -> Vec<String> {
-        let stop_words = ["the", "a", "an", "is", "are", "was", "were", "be", "been",
-            "being", "have", "has", "had", "do", "does", "did", "will", "would",
-            "could", "should", "may", "might", "must", "shall", "can", "need",
-            "to", "of", "in", "for", "on", "with", "at", "by", "from", "as",
-            "into", "through", "during", "before", "after", "above", "below",
-            "between", "under", "again", "further", "then", "once", "here",
-            "there", "when", "where", "why", "how", "all", "each", "few",
-            "more", "most", "other", "some", "such", "no", "nor", "not",
-            "only", "own", "same", "so", "than", "too", "very", "just"];
-
-        let mut word_count: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-
-        for word in content.split_whitespace() {
-            let cleaned: String = word
-                .chars()
-                .filter(|c| c.is_alphanumeric())
-                .collect::<String>()
-                .to_lowercase();
-
-            if cleaned.len() > 2 && !stop_words.contains(&cleaned.as_str()) {
-                *word_count.entry(cleaned).or_insert(0) += 1;
-            }
-        }
-
-        let mut words: Vec<_> = word_count.into_iter().collect();
-        words.sort_by(|a, b| b.1.cmp(&a.1));
+        Tokenizer::keywords(content, max_keywords)
+    }
+}
 
-        words.into_iter()
-            .take(max_keywords)
-            .map(|(word, _)| word)
-            .collect()
+/// 判断 `text` 中是否出现完整的 `word`（英文按单词边界，中文直接包含）
+fn contains_word(text: &str, word: &str) -> bool {
+    if !word.is_ascii() {
+        return text.contains(word);
     }
+
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        let is_boundary = |c: Option<char>| c.map(|c| !c.is_ascii_alphanumeric()).unwrap_or(true);
+        is_boundary(before) && is_boundary(after)
+    })
 }
@@ -0,0 +1,348 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// 报错原文保留的最大字符数
+const MAX_MESSAGE_CHARS: usize = 300;
+/// rustc 报错标题之后查找 `-->` 位置的行数
+const RUSTC_LOCATION_LINES: usize = 4;
+
+/// 从报错文字中解析出的结构化错误
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IssueInfo {
+    /// 报错来源：rustc、cargo、tsc、eslint、python、java、npm、http
+    pub tool: String,
+    /// 错误码、规则名或异常类型，如 E0308、TS2345、no-unused-vars、KeyError、E404、502
+    #[serde(default)]
+    pub code: String,
+    /// 出错的文件；HTTP 错误为请求地址（不含查询参数）
+    #[serde(default)]
+    pub file: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    /// 报错原文
+    #[serde(default)]
+    pub message: String,
+}
+
+impl IssueInfo {
+    /// 提醒去重键：来源、错误码加文件，不含行号（改代码后行号会变）；错误码和文件都没有时返回 None
+    pub fn dedupe_key(&self) -> Option<String> {
+        if self.code.is_empty() && self.file.is_empty() {
+            return None;
+        }
+        Some(format!("{}:{}:{}", self.tool, self.code, normalize_path(&self.file)).to_lowercase())
+    }
+
+    /// 出错位置，如 `src/main.rs:10:5`
+    pub fn location(&self) -> String {
+        let mut location = self.file.clone();
+        if let Some(line) = self.line {
+            location.push_str(&format!(":{}", line));
+            if let Some(column) = self.column {
+                location.push_str(&format!(":{}", column));
+            }
+        }
+        location
+    }
+
+    /// 单行描述，如 `rustc E0308 src/main.rs:10:5 mismatched types`
+    pub fn describe(&self) -> String {
+        [self.tool.clone(), self.code.clone(), self.location(), self.message.clone()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// 识别编译器、测试工具和运行时输出的报错格式
+pub struct IssueParser;
+
+impl IssueParser {
+    /// 依次尝试各类报错格式，返回识别出的第一处错误
+    pub fn parse(text: &str) -> Option<IssueInfo> {
+        if text.trim().is_empty() {
+            return None;
+        }
+        parse_rustc(text)
+            .or_else(|| parse_tsc(text))
+            .or_else(|| parse_eslint(text))
+            .or_else(|| parse_python(text))
+            .or_else(|| parse_java(text))
+            .or_else(|| parse_npm(text))
+            .or_else(|| parse_http(text))
+    }
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("内置报错规则无效"))
+}
+
+/// `error[E0308]: mismatched types` 加上后几行的 `--> src/main.rs:10:5`；
+/// 只有 `could not compile` 这类汇总时记为 cargo 错误
+fn parse_rustc(text: &str) -> Option<IssueInfo> {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let header = regex(&HEADER, r"(?m)\berror(?:\[(?P<code>E\d{4})\])?:[ \t]*(?P<msg>[^\n]+)");
+    let location = regex(&LOCATION, r"-->\s*(?P<file>[^\n]+?):(?P<line>\d+):(?P<col>\d+)");
+
+    let mut cargo = None;
+    for caps in header.captures_iter(text) {
+        let whole = caps.get(0)?;
+        let raw_message = &caps["msg"];
+        // 模型或识别结果可能把位置和标题写在同一行
+        let (message, inline) = match raw_message.find("-->") {
+            Some(index) => (&raw_message[..index], &raw_message[index..]),
+            None => (raw_message, ""),
+        };
+        let following: String = text[whole.end()..]
+            .lines()
+            .take(RUSTC_LOCATION_LINES)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let position = location
+            .captures(inline)
+            .or_else(|| location.captures(&following));
+        let code = caps.name("code").map(|m| m.as_str().to_string());
+
+        if code.is_none() && position.is_none() {
+            let summary = message.trim();
+            if cargo.is_none() && (summary.starts_with("could not compile") || summary.starts_with("no matching package")) {
+                cargo = Some(IssueInfo {
+                    tool: "cargo".to_string(),
+                    message: clip(summary),
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+
+        return Some(IssueInfo {
+            tool: "rustc".to_string(),
+            code: code.unwrap_or_default(),
+            file: position.as_ref().map(|p| p["file"].trim().to_string()).unwrap_or_default(),
+            line: position.as_ref().and_then(|p| p["line"].parse().ok()),
+            column: position.as_ref().and_then(|p| p["col"].parse().ok()),
+            message: clip(message),
+        });
+    }
+    cargo
+}
+
+/// `src/app.ts(12,5): error TS2345: ...`、`src/app.ts:12:5 - error TS2345: ...` 或单独的 `error TS2345: ...`
+fn parse_tsc(text: &str) -> Option<IssueInfo> {
+    static LOCATED: OnceLock<Regex> = OnceLock::new();
+    static BARE: OnceLock<Regex> = OnceLock::new();
+    let located = regex(
+        &LOCATED,
+        r"(?P<file>[^\s():]+\.(?:ts|tsx|mts|cts|js|jsx|vue))(?:\((?P<line>\d+),(?P<col>\d+)\)|:(?P<line2>\d+):(?P<col2>\d+))\s*[:-]?\s*error\s+(?P<code>TS\d+):\s*(?P<msg>[^\n]+)",
+    );
+    let bare = regex(&BARE, r"\berror\s+(?P<code>TS\d+):\s*(?P<msg>[^\n]+)");
+
+    if let Some(caps) = located.captures(text) {
+        let number = |a: &str, b: &str| caps.name(a).or_else(|| caps.name(b)).and_then(|m| m.as_str().parse().ok());
+        return Some(IssueInfo {
+            tool: "tsc".to_string(),
+            code: caps["code"].to_string(),
+            file: caps["file"].to_string(),
+            line: number("line", "line2"),
+            column: number("col", "col2"),
+            message: clip(&caps["msg"]),
+        });
+    }
+    bare.captures(text).map(|caps| IssueInfo {
+        tool: "tsc".to_string(),
+        code: caps["code"].to_string(),
+        message: clip(&caps["msg"]),
+        ..Default::default()
+    })
+}
+
+/// 默认的 stylish 格式（文件名单独一行，下面是 `12:5  error  消息  规则名`）和 compact 格式
+fn parse_eslint(text: &str) -> Option<IssueInfo> {
+    static STYLISH: OnceLock<Regex> = OnceLock::new();
+    static FILE_LINE: OnceLock<Regex> = OnceLock::new();
+    static COMPACT: OnceLock<Regex> = OnceLock::new();
+    let stylish = regex(
+        &STYLISH,
+        r"^\s*(?P<line>\d+):(?P<col>\d+)\s+error\s+(?P<msg>.+?)\s+(?P<code>@?[A-Za-z][\w-]*(?:/[\w-]+)*)\s*$",
+    );
+    let file_line = regex(&FILE_LINE, r"^\s*(?P<file>\S[^\n]*\.(?:js|jsx|ts|tsx|mjs|cjs|vue))\s*$");
+    let compact = regex(
+        &COMPACT,
+        r"(?m)(?P<file>\S+): line (?P<line>\d+), col (?P<col>\d+), Error - (?P<msg>.+?)(?: \((?P<code>[^)\n]+)\))?\s*$",
+    );
+
+    let mut current_file = "";
+    for line in text.lines() {
+        if let Some(caps) = file_line.captures(line) {
+            current_file = caps.name("file").map(|m| m.as_str()).unwrap_or_default();
+            continue;
+        }
+        if let Some(caps) = stylish.captures(line) {
+            return Some(IssueInfo {
+                tool: "eslint".to_string(),
+                code: caps["code"].to_string(),
+                file: current_file.to_string(),
+                line: caps["line"].parse().ok(),
+                column: caps["col"].parse().ok(),
+                message: clip(&caps["msg"]),
+            });
+        }
+    }
+
+    compact.captures(text).map(|caps| IssueInfo {
+        tool: "eslint".to_string(),
+        code: caps.name("code").map(|m| m.as_str().to_string()).unwrap_or_default(),
+        file: caps["file"].to_string(),
+        line: caps["line"].parse().ok(),
+        column: caps["col"].parse().ok(),
+        message: clip(&caps["msg"]),
+    })
+}
+
+/// Python 调用栈：取最后一个 `File "...", line N` 作为位置，其后的 `XxxError: 消息` 作为异常
+fn parse_python(text: &str) -> Option<IssueInfo> {
+    static FRAME: OnceLock<Regex> = OnceLock::new();
+    static EXCEPTION: OnceLock<Regex> = OnceLock::new();
+    let frame = regex(&FRAME, r#"File "(?P<file>[^"\n]+)", line (?P<line>\d+)"#);
+    let exception = regex(
+        &EXCEPTION,
+        r"(?m)^\s*(?P<code>[A-Za-z_][\w.]*(?:Error|Exception|Exit|Interrupt|Warning))(?::[ \t]*(?P<msg>[^\n]*))?\s*$",
+    );
+
+    let marker = text.find("Traceback (most recent call last)");
+    let last_frame = frame.captures_iter(text).last();
+    // 没有调用栈时，单独的 `XxxError:` 也可能是其他语言的报错，不按 Python 处理
+    let search_from = match (&last_frame, marker) {
+        (Some(caps), _) => caps.get(0)?.end(),
+        (None, Some(index)) => index,
+        (None, None) => return None,
+    };
+
+    let caps = exception.captures(&text[search_from..])?;
+    Some(IssueInfo {
+        tool: "python".to_string(),
+        code: caps["code"].to_string(),
+        file: last_frame.as_ref().map(|f| f["file"].to_string()).unwrap_or_default(),
+        line: last_frame.as_ref().and_then(|f| f["line"].parse().ok()),
+        column: None,
+        message: clip(caps.name("msg").map(|m| m.as_str()).unwrap_or_default()),
+    })
+}
+
+/// Java 异常：`java.lang.IllegalStateException: 消息` 加 `at com.x.Foo.bar(Foo.java:42)`；
+/// 有 `Caused by:` 时取最底层的原因
+fn parse_java(text: &str) -> Option<IssueInfo> {
+    static EXCEPTION: OnceLock<Regex> = OnceLock::new();
+    static FRAME: OnceLock<Regex> = OnceLock::new();
+    let exception = regex(
+        &EXCEPTION,
+        r"(?P<cause>Caused by:\s*)?(?P<code>(?:[a-z_$][\w$]*\.)+[A-Z][\w$]*(?:Exception|Error|Throwable))(?::[ \t]*(?P<msg>[^\n]*))?",
+    );
+    let frame = regex(
+        &FRAME,
+        r"\bat\s+[\w$.<>/]+\((?P<file>[\w$-]+\.(?:java|kt|scala|groovy)):(?P<line>\d+)\)",
+    );
+
+    let caps = exception
+        .captures_iter(text)
+        .filter(|caps| caps.name("cause").is_some())
+        .last()
+        .or_else(|| exception.captures(text))?;
+    // 位置取异常之后的第一帧
+    let end = caps.get(0)?.end();
+    let position = frame.captures(&text[end..]).or_else(|| frame.captures(text));
+
+    Some(IssueInfo {
+        tool: "java".to_string(),
+        code: caps["code"].to_string(),
+        file: position.as_ref().map(|p| p["file"].to_string()).unwrap_or_default(),
+        line: position.as_ref().and_then(|p| p["line"].parse().ok()),
+        column: None,
+        message: clip(caps.name("msg").map(|m| m.as_str()).unwrap_or_default()),
+    })
+}
+
+/// `npm ERR! code ERESOLVE`（新版为 `npm error code ...`），消息取第一条说明行
+fn parse_npm(text: &str) -> Option<IssueInfo> {
+    static LINE: OnceLock<Regex> = OnceLock::new();
+    let line = regex(&LINE, r"(?m)^\s*npm (?:ERR!|error)[ \t]+(?P<body>[^\n]*)$");
+
+    let mut issue = IssueInfo {
+        tool: "npm".to_string(),
+        ..Default::default()
+    };
+    let mut found = false;
+    for caps in line.captures_iter(text) {
+        found = true;
+        let body = caps["body"].trim();
+        if let Some(code) = body.strip_prefix("code ") {
+            if issue.code.is_empty() {
+                issue.code = code.trim().to_string();
+            }
+        } else if let Some(path) = body.strip_prefix("path ") {
+            if issue.file.is_empty() {
+                issue.file = path.trim().to_string();
+            }
+        } else if issue.message.is_empty()
+            && !body.is_empty()
+            && !["errno ", "syscall ", "A complete log", "Log files", "dest "]
+                .iter()
+                .any(|prefix| body.starts_with(prefix))
+        {
+            issue.message = clip(body);
+        }
+    }
+    found.then_some(issue)
+}
+
+/// `HTTP/1.1 502 Bad Gateway`、`404 Not Found`、`status code 500` 等；地址取同一行中的 URL
+fn parse_http(text: &str) -> Option<IssueInfo> {
+    static PHRASE: OnceLock<Regex> = OnceLock::new();
+    static MARKED: OnceLock<Regex> = OnceLock::new();
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let phrase = regex(
+        &PHRASE,
+        r"(?i)\b(?P<code>[45]\d{2})\b[ \t]*[-:]?[ \t]*(?:Bad Request|Unauthorized|Payment Required|Forbidden|Not Found|Method Not Allowed|Not Acceptable|Request Timeout|Conflict|Gone|Payload Too Large|Unsupported Media Type|Unprocessable (?:Entity|Content)|Too Many Requests|Internal Server Error|Not Implemented|Bad Gateway|Service Unavailable|Gateway Timeout)\b",
+    );
+    let marked = regex(&MARKED, r"(?i)(?:status(?:[ \t]*code)?|HTTP(?:/\d(?:\.\d)?)?|状态码)[ \t]*[:=：]?[ \t]*(?P<code>[45]\d{2})\b");
+    let url = regex(&URL, r#"https?://[^\s"'<>()\[\]]+"#);
+
+    let caps = phrase.captures(text).or_else(|| marked.captures(text))?;
+    let code = caps.name("code")?;
+    let line_start = text[..code.start()].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line_end = text[code.end()..].find('\n').map(|i| code.end() + i).unwrap_or(text.len());
+    let line = &text[line_start..line_end];
+    let address = url
+        .find(line)
+        .map(|m| m.as_str().split(['?', '#']).next().unwrap_or_default().to_string())
+        .unwrap_or_default();
+
+    Some(IssueInfo {
+        tool: "http".to_string(),
+        code: code.as_str().to_string(),
+        file: address,
+        line: None,
+        column: None,
+        message: clip(line),
+    })
+}
+
+fn normalize_path(path: &str) -> String {
+    let path = path.trim().replace('\\', "/");
+    path.strip_prefix("./").unwrap_or(&path).to_string()
+}
+
+fn clip(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_MESSAGE_CHARS {
+        return text.to_string();
+    }
+    let mut clipped: String = text.chars().take(MAX_MESSAGE_CHARS).collect();
+    clipped.push('…');
+    clipped
+}
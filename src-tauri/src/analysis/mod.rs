@@ -1,5 +1,17 @@
 pub mod diff;
 pub mod extractor;
+pub mod issue;
+pub mod rules;
+pub mod scrub;
+pub mod session;
+pub mod stats;
+pub mod tokenizer;
 
 pub use diff::*;
 pub use extractor::*;
+pub use issue::*;
+pub use rules::*;
+pub use scrub::*;
+pub use session::*;
+pub use stats::*;
+pub use tokenizer::*;
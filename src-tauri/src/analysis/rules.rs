@@ -0,0 +1,257 @@
+use super::{ActivityInfo, InfoExtractor};
+use crate::storage::SummaryRecord;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// 规则测试时最多返回的样例数
+const MAX_TEST_SAMPLES: usize = 20;
+
+/// 用户自定义的活动分类规则
+///
+/// 条件为正则（不区分大小写），填写的条件需全部命中；命中后按规则给出活动类型、项目或应用名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityRule {
+    #[serde(default)]
+    pub name: String,
+    /// 数值越大越先匹配，相同时按文件中的顺序
+    #[serde(default)]
+    pub priority: i32,
+    /// 匹配应用名（模型给出的原始名称和归一化名称）
+    #[serde(default)]
+    pub app: Option<String>,
+    /// 匹配窗口标题（原始应用名）和摘要
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 匹配画面描述
+    #[serde(default)]
+    pub detail: Option<String>,
+    /// 命中后的活动类型
+    #[serde(default)]
+    pub activity_type: Option<String>,
+    /// 命中后的项目名
+    #[serde(default)]
+    pub project: Option<String>,
+    /// 命中后替换的应用名
+    #[serde(default)]
+    pub app_name: Option<String>,
+}
+
+/// activity_rules.json 的内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityRulesFile {
+    #[serde(default)]
+    pub rules: Vec<ActivityRule>,
+}
+
+/// 编译后的一条规则
+struct CompiledRule {
+    priority: i32,
+    app: Option<Regex>,
+    title: Option<Regex>,
+    detail: Option<Regex>,
+    activity_type: Option<String>,
+    project: Option<String>,
+    app_name: Option<String>,
+}
+
+impl ActivityRule {
+    fn compile(&self) -> Result<CompiledRule, String> {
+        let label = if self.name.trim().is_empty() { "未命名规则" } else { self.name.trim() };
+        let pattern = |value: &Option<String>| -> Result<Option<Regex>, String> {
+            match value.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+                Some(p) => Regex::new(&format!("(?i){}", p))
+                    .map(Some)
+                    .map_err(|e| format!("规则「{}」的正则无效 {}: {}", label, p, e)),
+                None => Ok(None),
+            }
+        };
+        let output = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string())
+        };
+
+        let compiled = CompiledRule {
+            priority: self.priority,
+            app: pattern(&self.app)?,
+            title: pattern(&self.title)?,
+            detail: pattern(&self.detail)?,
+            activity_type: output(&self.activity_type),
+            project: output(&self.project),
+            app_name: output(&self.app_name),
+        };
+        if compiled.app.is_none() && compiled.title.is_none() && compiled.detail.is_none() {
+            return Err(format!("规则「{}」至少需要 app、title、detail 中的一个条件", label));
+        }
+        if compiled.activity_type.is_none() && compiled.project.is_none() && compiled.app_name.is_none() {
+            return Err(format!("规则「{}」至少需要 activity_type、project、app_name 中的一个结果", label));
+        }
+        Ok(compiled)
+    }
+}
+
+impl CompiledRule {
+    fn matches(&self, raw_app: &str, normalized_app: &str, summary: &str, detail: &str) -> bool {
+        let app_ok = self
+            .app
+            .as_ref()
+            .map(|re| re.is_match(raw_app) || re.is_match(normalized_app))
+            .unwrap_or(true);
+        let title_ok = self
+            .title
+            .as_ref()
+            .map(|re| re.is_match(raw_app) || re.is_match(summary))
+            .unwrap_or(true);
+        let detail_ok = self.detail.as_ref().map(|re| re.is_match(detail)).unwrap_or(true);
+        app_ok && title_ok && detail_ok
+    }
+}
+
+/// 按优先级排好序的分类规则，没有规则命中的字段使用内置识别结果
+#[derive(Default)]
+pub struct ActivityRules {
+    rules: Vec<CompiledRule>,
+}
+
+impl ActivityRules {
+    /// 编译规则；无效的规则跳过并输出原因
+    pub fn new(rules: &[ActivityRule]) -> Self {
+        let mut compiled: Vec<CompiledRule> = rules
+            .iter()
+            .filter_map(|rule| match rule.compile() {
+                Ok(compiled) => Some(compiled),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    None
+                }
+            })
+            .collect();
+        // 稳定排序，优先级相同的保持文件中的顺序
+        compiled.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        Self { rules: compiled }
+    }
+
+    /// 读取规则文件；文件不存在时没有自定义规则
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path).map_err(|e| format!("读取分类规则失败: {}", e))?;
+        let file: ActivityRulesFile =
+            serde_json::from_str(&content).map_err(|e| format!("解析分类规则失败: {}", e))?;
+        Ok(Self::new(&file.rules))
+    }
+
+    /// 先用内置规则识别，再按优先级应用自定义规则；每个字段取第一条给出该字段的规则
+    pub fn classify(&self, app: &str, summary: &str, detail: &str) -> ActivityInfo {
+        let mut info = InfoExtractor::analyze(app, summary, detail);
+        let mut activity_type = None;
+        let mut app_name = None;
+
+        for rule in &self.rules {
+            if !rule.matches(app, &info.app, summary, detail) {
+                continue;
+            }
+            if activity_type.is_none() {
+                activity_type = rule.activity_type.clone();
+            }
+            if info.project.is_none() {
+                info.project = rule.project.clone();
+            }
+            if app_name.is_none() {
+                app_name = rule.app_name.clone();
+            }
+            if activity_type.is_some() && info.project.is_some() && app_name.is_some() {
+                break;
+            }
+        }
+
+        if let Some(name) = app_name {
+            // 应用名被改写且没有规则指定活动类型时，按新应用名重新推断
+            if activity_type.is_none() {
+                let content = format!("{}\n{}", summary, detail);
+                activity_type = Some(InfoExtractor::infer_activity_type(&name, app, &content));
+            }
+            info.app = name;
+        }
+        if let Some(activity_type) = activity_type {
+            info.activity_type = activity_type;
+        }
+        info
+    }
+}
+
+/// 规则测试中一条命中记录的前后对比
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestSample {
+    pub timestamp: String,
+    pub app: String,
+    pub summary: String,
+    pub activity_type: String,
+    pub project: String,
+    pub new_activity_type: String,
+    pub new_project: String,
+    pub new_app_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleTestResult {
+    /// 参与测试的记录数
+    pub total: usize,
+    /// 命中规则的记录数
+    pub matched: usize,
+    /// 分类结果会变化的记录数
+    pub changed: usize,
+    /// 最近命中的记录（最多 20 条）
+    pub samples: Vec<RuleTestSample>,
+}
+
+/// 用单条规则对历史记录试跑，不修改记录
+pub fn test_activity_rule(rule: &ActivityRule, records: &[SummaryRecord]) -> Result<RuleTestResult, String> {
+    let compiled = rule.compile()?;
+    let rules = ActivityRules { rules: vec![compiled] };
+
+    let mut result = RuleTestResult {
+        total: 0,
+        matched: 0,
+        changed: 0,
+        samples: Vec::new(),
+    };
+    // 从最新的记录开始，样例优先展示最近的命中
+    for record in records.iter().rev() {
+        // 状态记录和隐私占位没有画面内容
+        if record.app.is_empty() || record.action == "private" {
+            continue;
+        }
+        result.total += 1;
+
+        let normalized = InfoExtractor::normalize_app(&record.app);
+        if !rules.rules[0].matches(&record.app, &normalized, &record.summary, &record.detail) {
+            continue;
+        }
+        result.matched += 1;
+
+        let info = rules.classify(&record.app, &record.summary, &record.detail);
+        let project = info.project.unwrap_or_default();
+        if info.activity_type != record.activity_type || project != record.project || info.app != record.app_label() {
+            result.changed += 1;
+        }
+        if result.samples.len() < MAX_TEST_SAMPLES {
+            result.samples.push(RuleTestSample {
+                timestamp: record.timestamp.clone(),
+                app: record.app_label().to_string(),
+                summary: record.summary.clone(),
+                activity_type: record.activity_type.clone(),
+                project: record.project.clone(),
+                new_activity_type: info.activity_type,
+                new_project: project,
+                new_app_name: info.app,
+            });
+        }
+    }
+    Ok(result)
+}
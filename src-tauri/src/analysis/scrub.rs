@@ -0,0 +1,150 @@
+use crate::storage::CaptureConfig;
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+/// 脱敏规则：命中后替换为带类型的占位符
+///
+/// 规则里有名为 `secret` 的捕获组时只替换该组（如连接串里的密码），否则替换整段匹配
+struct ScrubRule {
+    pattern: Regex,
+    placeholder: String,
+}
+
+/// 内置规则，按顺序执行；长格式（私钥、JWT）放在前面，避免被短规则拆开
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        r"(?s)-----BEGIN [A-Z ]*PRIVATE KEY-----.*?(?:-----END [A-Z ]*PRIVATE KEY-----|\z)",
+        "[PRIVATE_KEY]",
+    ),
+    (
+        r"eyJ[A-Za-z0-9_-]{5,}\.eyJ[A-Za-z0-9_-]{5,}\.[A-Za-z0-9_-]{5,}",
+        "[JWT]",
+    ),
+    (r"(?-u:\b)(?:AKIA|ASIA)[0-9A-Z]{16}(?-u:\b)", "[AWS_KEY]"),
+    (
+        r"(?-u:\b)(?:sk-[A-Za-z0-9_-]{16,}|sk_(?:live|test)_[A-Za-z0-9]{16,}|gh[pousr]_[A-Za-z0-9]{36}|github_pat_[A-Za-z0-9_]{22,}|xox[abprs]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})",
+        "[API_KEY]",
+    ),
+    (
+        r#"[A-Za-z][A-Za-z0-9+.-]*://[^\s:/@"']+:(?P<secret>[^\s@/"']+)@"#,
+        "[PASSWORD]",
+    ),
+    (
+        r#"(?i)(?:password|passwd|pwd|secret|token|api[_-]?key|access[_-]?key)["']?\s*[:=]\s*["']?(?P<secret>[^\s"',;]{6,})"#,
+        "[SECRET]",
+    ),
+    (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "[EMAIL]"),
+    (
+        r"(?:^|[^\d])(?P<secret>(?:\+?86[\s-]?)?1[3-9]\d{9})(?:$|[^\d])",
+        "[PHONE]",
+    ),
+    (
+        r"\+\d{1,3}[\s-]?\(?\d{1,4}\)?(?:[\s-]?\d{2,4}){2,4}",
+        "[PHONE]",
+    ),
+    (r"\(\d{3}\)\s?\d{3}-\d{4}", "[PHONE]"),
+];
+
+/// 敏感信息脱敏
+///
+/// 在摘要落盘和写请求日志之前，把模型从屏幕上读到的密钥、邮箱、手机号等替换为占位符
+pub struct Scrubber {
+    enabled: bool,
+    custom: Vec<ScrubRule>,
+}
+
+impl Scrubber {
+    pub fn from_config(config: &CaptureConfig) -> Self {
+        let custom = config
+            .scrub_patterns
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .filter_map(|p| match Regex::new(p) {
+                Ok(pattern) => Some(ScrubRule {
+                    pattern,
+                    placeholder: "[REDACTED]".to_string(),
+                }),
+                Err(err) => {
+                    eprintln!("脱敏规则无效 {}: {}", p, err);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            enabled: config.scrub_secrets,
+            custom,
+        }
+    }
+
+    pub fn scrub(&self, text: &str) -> String {
+        if !self.enabled || text.is_empty() {
+            return text.to_string();
+        }
+
+        let mut result = text.to_string();
+        for rule in builtin_rules().iter().chain(self.custom.iter()) {
+            if rule.pattern.is_match(&result) {
+                result = rule
+                    .pattern
+                    .replace_all(&result, |caps: &Captures| replace_match(caps, &rule.placeholder))
+                    .into_owned();
+            }
+        }
+        result
+    }
+
+    /// 请求日志脱敏：跳过其中的 base64 图片，只处理其余文本
+    ///
+    /// 图片数据里可能恰好出现形似手机号的数字串，替换后日志中的图片就无法还原，逐条规则扫描也很慢
+    pub fn scrub_log(&self, text: &str) -> String {
+        if !self.enabled || text.is_empty() {
+            return text.to_string();
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for image in image_payload_pattern().find_iter(text) {
+            result.push_str(&self.scrub(&text[last..image.start()]));
+            result.push_str(image.as_str());
+            last = image.end();
+        }
+        result.push_str(&self.scrub(&text[last..]));
+        result
+    }
+}
+
+/// 请求中的图片：`data:image/...;base64,` 开头的数据，或 Ollama 直接放入 images 的长 base64 串
+fn image_payload_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"data:image/[\w.+-]+;base64,[A-Za-z0-9+/]*={0,2}|[A-Za-z0-9+/]{1024,}={0,2}")
+            .expect("图片数据规则无效")
+    })
+}
+
+fn builtin_rules() -> &'static [ScrubRule] {
+    static RULES: OnceLock<Vec<ScrubRule>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        BUILTIN_RULES
+            .iter()
+            .map(|(pattern, placeholder)| ScrubRule {
+                pattern: Regex::new(pattern).expect("内置脱敏规则无效"),
+                placeholder: placeholder.to_string(),
+            })
+            .collect()
+    })
+}
+
+fn replace_match(caps: &Captures, placeholder: &str) -> String {
+    let whole = caps.get(0).expect("整段匹配必然存在");
+    match caps.name("secret") {
+        Some(secret) => {
+            let text = whole.as_str();
+            let start = secret.start() - whole.start();
+            let end = secret.end() - whole.start();
+            format!("{}{}{}", &text[..start], placeholder, &text[end..])
+        }
+        None => placeholder.to_string(),
+    }
+}
@@ -0,0 +1,325 @@
+use super::Tokenizer;
+use crate::storage::{parse_timestamp, StorageConfig, SummaryRecord};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 判断话题是否变化时参考的本段最近记录数
+const TOPIC_WINDOW: usize = 5;
+/// 每个工作段保留的代表性摘要数
+const MAX_HIGHLIGHTS: usize = 5;
+/// 每个工作段保留的文件数
+const MAX_SESSION_FILES: usize = 5;
+/// 临时标题的最大字符数
+const FALLBACK_TITLE_CHARS: usize = 24;
+
+/// 一段连续的工作（任务），由相邻记录按空闲间隔、应用/项目切换和话题变化切分得到
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkSession {
+    pub start: String,
+    pub end: String,
+    /// 模型生成的标题；还没有生成或生成失败时为按摘要拼出的临时标题
+    #[serde(default)]
+    pub title: String,
+    /// 标题是否由模型生成，临时标题在下次查询时重试
+    #[serde(default)]
+    pub title_from_model: bool,
+    /// 停留时间最长的应用、项目和活动类型
+    #[serde(default)]
+    pub app: String,
+    #[serde(default)]
+    pub project: String,
+    #[serde(default)]
+    pub activity_type: String,
+    /// 用到的应用，按停留时间排序
+    #[serde(default)]
+    pub apps: Vec<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// 停留最久的几条摘要，按时间顺序
+    #[serde(default)]
+    pub highlights: Vec<String>,
+    /// 包含的帧数
+    #[serde(default)]
+    pub record_count: u32,
+    /// 不同问题的个数
+    #[serde(default)]
+    pub issue_count: u32,
+    #[serde(default)]
+    pub duration_seconds: i64,
+}
+
+impl WorkSession {
+    /// 起止时间，如 `10:02–10:41`
+    pub fn time_label(&self) -> String {
+        let clock = |value: &str| value.get(11..16).unwrap_or(value).to_string();
+        format!("{}–{}", clock(&self.start), clock(&self.end))
+    }
+}
+
+/// 工作段切分规则
+#[derive(Debug, Clone)]
+pub struct SessionPolicy {
+    /// 相邻记录间隔超过该值视为离开，另起一段
+    pub idle_seconds: i64,
+    /// 切换应用后与本段最近记录的摘要相似度低于该值视为换了任务；同一应用内按一半计算
+    pub topic_similarity: f64,
+    /// 切换持续超过该时长才另起一段，短暂切过去又回来仍算在当前段
+    pub switch_seconds: i64,
+}
+
+impl SessionPolicy {
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self {
+            idle_seconds: config.session_idle_seconds.max(60) as i64,
+            topic_similarity: config.session_topic_similarity.clamp(0.0, 1.0),
+            switch_seconds: config.session_switch_seconds as i64,
+        }
+    }
+}
+
+/// 把一天的记录切分为工作段
+pub struct Sessionizer;
+
+impl Sessionizer {
+    /// 按时间顺序切分；暂停、不在监控时段等状态记录结束当前段
+    pub fn split(records: &[SummaryRecord], policy: &SessionPolicy) -> Vec<WorkSession> {
+        let mut ordered: Vec<&SummaryRecord> = records.iter().collect();
+        ordered.sort_by(|a, b| a.start_time().cmp(b.start_time()));
+
+        let mut groups: Vec<Vec<&SummaryRecord>> = Vec::new();
+        let mut current: Vec<&SummaryRecord> = Vec::new();
+        // 疑似切换到别的任务后的记录，持续够久才另起一段
+        let mut pending: Vec<&SummaryRecord> = Vec::new();
+
+        for record in ordered {
+            if record.app.is_empty() {
+                if record.action != "resumed" {
+                    current.append(&mut pending);
+                    close(&mut groups, &mut current);
+                }
+                continue;
+            }
+
+            let idle = pending
+                .last()
+                .or(current.last())
+                .map(|last| seconds_between(last.end_time(), record.start_time()) > policy.idle_seconds)
+                .unwrap_or(false);
+            if idle || switched_project(&current, record) {
+                current.append(&mut pending);
+                close(&mut groups, &mut current);
+                current.push(record);
+                continue;
+            }
+            if current.is_empty() {
+                current.push(record);
+                continue;
+            }
+            if record.action == "private" {
+                if pending.is_empty() {
+                    current.push(record);
+                } else {
+                    pending.push(record);
+                }
+                continue;
+            }
+
+            if diverges(&current, record, policy) {
+                pending.push(record);
+                let switched_for = seconds_between(pending[0].start_time(), &record.timestamp);
+                if switched_for >= policy.switch_seconds {
+                    close(&mut groups, &mut current);
+                    current = std::mem::take(&mut pending);
+                }
+            } else {
+                current.append(&mut pending);
+                current.push(record);
+            }
+        }
+        current.append(&mut pending);
+        close(&mut groups, &mut current);
+
+        groups.iter().map(|group| build_session(group)).collect()
+    }
+
+    /// 沿用已保存的模型标题：开始时间相同且记录数没有翻倍（任务基本没变）时不重新生成
+    pub fn reuse_titles(sessions: &mut [WorkSession], saved: &[WorkSession]) {
+        for session in sessions.iter_mut() {
+            let previous = saved
+                .iter()
+                .find(|s| s.start == session.start && s.title_from_model && !s.title.is_empty());
+            if let Some(previous) = previous {
+                if previous.record_count * 2 >= session.record_count {
+                    session.title = previous.title.clone();
+                    session.title_from_model = true;
+                }
+            }
+        }
+    }
+}
+
+fn close<'a>(groups: &mut Vec<Vec<&'a SummaryRecord>>, current: &mut Vec<&'a SummaryRecord>) {
+    // 只有隐私占位的段没有可展示的内容
+    if current.iter().any(|r| r.action != "private") {
+        groups.push(std::mem::take(current));
+    } else {
+        current.clear();
+    }
+}
+
+fn seconds_between(from: &str, to: &str) -> i64 {
+    match (parse_timestamp(from), parse_timestamp(to)) {
+        (Some(from), Some(to)) => (to - from).num_seconds(),
+        _ => 0,
+    }
+}
+
+/// 自定义规则给出的项目变了
+fn switched_project(current: &[&SummaryRecord], record: &SummaryRecord) -> bool {
+    if record.project.is_empty() {
+        return false;
+    }
+    current
+        .iter()
+        .rev()
+        .find(|r| !r.project.is_empty())
+        .map(|r| r.project != record.project)
+        .unwrap_or(false)
+}
+
+/// 与当前段相比是否像是换了任务：同一项目或同一文件不算；
+/// 换到本段没用过的应用时按相似度阈值判断，同一应用内话题差异更大才算
+fn diverges(current: &[&SummaryRecord], record: &SummaryRecord, policy: &SessionPolicy) -> bool {
+    if !record.project.is_empty() && current.iter().any(|r| r.project == record.project) {
+        return false;
+    }
+    if !record.file.is_empty() && current.iter().any(|r| r.file == record.file) {
+        return false;
+    }
+
+    let text = topic_text(record);
+    let similarity = current
+        .iter()
+        .rev()
+        .filter(|r| r.action != "private")
+        .take(TOPIC_WINDOW)
+        .map(|r| Tokenizer::similarity(&topic_text(r), &text))
+        .fold(0.0, f64::max);
+
+    let app_changed = !current.iter().any(|r| r.app_label() == record.app_label());
+    if app_changed {
+        similarity < policy.topic_similarity
+    } else {
+        similarity < policy.topic_similarity / 2.0
+    }
+}
+
+fn topic_text(record: &SummaryRecord) -> String {
+    format!("{} {}", record.summary, record.file)
+}
+
+fn build_session(records: &[&SummaryRecord]) -> WorkSession {
+    let first = records[0];
+    let last = records[records.len() - 1];
+
+    // 每条记录的停留时间：自身跨度加上到下一条记录的间隔（段内间隔都不超过空闲阈值）
+    let weights: Vec<i64> = records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let own = seconds_between(record.start_time(), record.end_time());
+            let gap = records
+                .get(index + 1)
+                .map(|next| seconds_between(record.end_time(), next.start_time()))
+                .unwrap_or(0);
+            (own + gap).max(1)
+        })
+        .collect();
+
+    let mut apps: HashMap<&str, i64> = HashMap::new();
+    let mut projects: HashMap<&str, i64> = HashMap::new();
+    let mut activities: HashMap<&str, i64> = HashMap::new();
+    let mut files: HashMap<&str, i64> = HashMap::new();
+    let mut summaries: HashMap<&str, (i64, usize)> = HashMap::new();
+    let mut issues: HashSet<String> = HashSet::new();
+    let mut record_count = 0;
+
+    for (index, (record, weight)) in records.iter().zip(&weights).enumerate() {
+        record_count += record.frames();
+        if record.action == "private" {
+            continue;
+        }
+        *apps.entry(record.app_label()).or_insert(0) += weight;
+        for (counts, value) in [
+            (&mut projects, record.project.as_str()),
+            (&mut activities, record.activity_type.as_str()),
+            (&mut files, record.file.as_str()),
+        ] {
+            if !value.is_empty() {
+                *counts.entry(value).or_insert(0) += weight;
+            }
+        }
+        if !record.summary.is_empty() {
+            summaries.entry(record.summary.as_str()).or_insert((0, index)).0 += weight;
+        }
+        if record.has_issue {
+            let key = record
+                .issue
+                .as_ref()
+                .and_then(|issue| issue.dedupe_key())
+                .unwrap_or_else(|| format!("{}:{}", record.issue_type, record.issue_summary));
+            issues.insert(key);
+        }
+    }
+
+    let mut highlights: Vec<(&str, i64, usize)> = summaries
+        .into_iter()
+        .map(|(summary, (weight, index))| (summary, weight, index))
+        .collect();
+    highlights.sort_by_key(|(_, weight, index)| (std::cmp::Reverse(*weight), *index));
+    highlights.truncate(MAX_HIGHLIGHTS);
+    let main_summary = highlights.first().map(|(summary, _, _)| summary.to_string());
+    highlights.sort_by_key(|(_, _, index)| *index);
+    let highlights: Vec<String> = highlights.into_iter().map(|(s, _, _)| s.to_string()).collect();
+
+    let apps = ranked(apps, usize::MAX);
+    let project = ranked(projects, 1).pop().unwrap_or_default();
+    let mut session = WorkSession {
+        start: first.start_time().to_string(),
+        end: last.end_time().to_string(),
+        title: String::new(),
+        title_from_model: false,
+        app: apps.first().cloned().unwrap_or_default(),
+        project,
+        activity_type: ranked(activities, 1).pop().unwrap_or_default(),
+        apps,
+        files: ranked(files, MAX_SESSION_FILES),
+        highlights,
+        record_count,
+        issue_count: issues.len() as u32,
+        duration_seconds: seconds_between(first.start_time(), last.end_time()).max(0),
+    };
+    session.title = fallback_title(&session, main_summary.as_deref());
+    session
+}
+
+/// 按停留时间从多到少排列，时间相同时按名称
+fn ranked(counts: HashMap<&str, i64>, limit: usize) -> Vec<String> {
+    let mut items: Vec<(&str, i64)> = counts.into_iter().collect();
+    items.sort_by_key(|(name, weight)| (std::cmp::Reverse(*weight), *name));
+    items.into_iter().take(limit).map(|(name, _)| name.to_string()).collect()
+}
+
+/// 模型标题生成前的临时标题：停留最久的摘要，前面加上项目名
+fn fallback_title(session: &WorkSession, main_summary: Option<&str>) -> String {
+    let summary = main_summary.unwrap_or(session.app.as_str());
+    let mut title: String = summary.chars().take(FALLBACK_TITLE_CHARS).collect();
+    if summary.chars().count() > FALLBACK_TITLE_CHARS {
+        title.push('…');
+    }
+    if session.project.is_empty() {
+        title
+    } else {
+        format!("[{}] {}", session.project, title)
+    }
+}
@@ -0,0 +1,271 @@
+use crate::storage::{parse_timestamp, SummaryRecord};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 单次统计最多覆盖的天数
+const MAX_STATS_DAYS: i64 = 366;
+/// 没有应用、活动类型或项目时的分组名
+const UNGROUPED: &str = "未分类";
+
+/// 统计的日期范围（含首尾两天）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatsRange {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl StatsRange {
+    /// 支持 `today`、`yesterday`、`week`（最近 7 天）、`month`（最近 30 天）、
+    /// `YYYY-MM-DD` 和 `YYYY-MM-DD..YYYY-MM-DD`
+    pub fn parse(value: &str, today: NaiveDate) -> Result<Self, String> {
+        let value = value.trim().to_lowercase();
+        let recent = |days: i64| Self {
+            start: today - Duration::days(days - 1),
+            end: today,
+        };
+        let range = match value.as_str() {
+            "" | "today" => recent(1),
+            "yesterday" => {
+                let day = today - Duration::days(1);
+                Self { start: day, end: day }
+            }
+            "week" => recent(7),
+            "month" => recent(30),
+            other => {
+                let parse_date = |s: &str| {
+                    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                        .map_err(|_| format!("无效的统计范围: {}", value))
+                };
+                match other.split_once("..") {
+                    Some((start, end)) => Self {
+                        start: parse_date(start)?,
+                        end: parse_date(end)?,
+                    },
+                    None => {
+                        let day = parse_date(other)?;
+                        Self { start: day, end: day }
+                    }
+                }
+            }
+        };
+
+        if range.end < range.start {
+            return Err(format!("统计范围的结束日期早于开始日期: {}", value));
+        }
+        if (range.end - range.start).num_days() >= MAX_STATS_DAYS {
+            return Err(format!("统计范围最多 {} 天", MAX_STATS_DAYS));
+        }
+        Ok(range)
+    }
+
+    /// 范围内的日期，按时间顺序
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        self.start.iter_days().take_while(|day| *day <= self.end).collect()
+    }
+}
+
+/// 统计的分组方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsGroup {
+    App,
+    Activity,
+    Project,
+}
+
+impl StatsGroup {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "app" => Ok(Self::App),
+            "activity" | "activity_type" => Ok(Self::Activity),
+            "project" => Ok(Self::Project),
+            other => Err(format!("不支持的分组方式: {}（可选 app、activity、project）", other)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::App => "app",
+            Self::Activity => "activity",
+            Self::Project => "project",
+        }
+    }
+
+    fn key<'a>(&self, record: &'a SummaryRecord) -> &'a str {
+        let value = match self {
+            Self::App => record.app_label(),
+            Self::Activity => record.activity_type.as_str(),
+            Self::Project => record.project.as_str(),
+        };
+        if value.is_empty() {
+            UNGROUPED
+        } else {
+            value
+        }
+    }
+}
+
+/// 一个分组的用时
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsEntry {
+    pub name: String,
+    pub seconds: i64,
+    /// 占总用时的比例（0-1）
+    pub share: f64,
+    /// 包含的帧数
+    pub record_count: u32,
+}
+
+/// 一天中每个小时的用时，用于热力图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapDay {
+    pub date: String,
+    /// 24 个小时各自的用时（秒）
+    pub hours: Vec<i64>,
+    /// 每个小时用时最多的分组，没有记录时为空
+    pub top: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeStats {
+    pub start: String,
+    pub end: String,
+    pub group_by: String,
+    pub total_seconds: i64,
+    /// 按用时从多到少排列
+    pub groups: Vec<StatsEntry>,
+    pub heatmap: Vec<HeatmapDay>,
+}
+
+/// 按相邻记录的时间间隔统计用时
+///
+/// 每条记录从开始时间算到下一条记录（跳过的无变化画面都算在最近一次分析出的活动上），
+/// 最后一次看到该画面（`last_seen`）之后的间隔最多计 `idle_seconds`，超出部分视为离开；
+/// 暂停、不在监控时段等状态记录之后不计时，隐私占位记录之后的冷却期也不计时。
+/// 结果只统计 `range` 内、`until` 之前的部分，`records` 可以多带前一天的记录以接上跨零点的间隔
+pub fn compute_time_stats(
+    records: &[SummaryRecord],
+    range: StatsRange,
+    group_by: StatsGroup,
+    idle_seconds: i64,
+    until: NaiveDateTime,
+) -> TimeStats {
+    let range_start = range.start.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let range_end = (range.end + Duration::days(1))
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .min(until);
+    let idle = Duration::seconds(idle_seconds.max(0));
+
+    let mut timeline: Vec<(NaiveDateTime, NaiveDateTime, &SummaryRecord)> = records
+        .iter()
+        .filter_map(|record| {
+            let start = parse_timestamp(record.start_time())?;
+            let end = parse_timestamp(record.end_time()).unwrap_or(start).max(start);
+            Some((start, end, record))
+        })
+        .collect();
+    timeline.sort_by_key(|(start, _, _)| *start);
+
+    let dates = range.dates();
+    let mut groups: HashMap<&str, (i64, u32)> = HashMap::new();
+    let mut cells: HashMap<(NaiveDate, u32), HashMap<&str, i64>> = HashMap::new();
+    let mut total_seconds = 0;
+
+    for (index, (start, end, record)) in timeline.iter().enumerate() {
+        // 状态记录只用来结束上一条记录的计时
+        if record.app.is_empty() {
+            continue;
+        }
+        // 隐私冷却期内的帧都被跳过，不能算作占位记录或之前应用的用时
+        let tail_limit = if record.action == "private" { *end } else { *end + idle };
+        let until_next = timeline
+            .get(index + 1)
+            .map(|(next_start, _, _)| (*next_start).min(tail_limit))
+            .unwrap_or(tail_limit);
+
+        let from = (*start).max(range_start);
+        let to = until_next.min(range_end);
+        if to <= from {
+            continue;
+        }
+
+        let name = group_by.key(record);
+        let seconds = (to - from).num_seconds();
+        let entry = groups.entry(name).or_insert((0, 0));
+        entry.0 += seconds;
+        entry.1 += record.frames();
+        total_seconds += seconds;
+
+        for (hour_start, hour_seconds) in split_by_hour(from, to) {
+            *cells
+                .entry((hour_start.date(), hour_start.hour()))
+                .or_default()
+                .entry(name)
+                .or_insert(0) += hour_seconds;
+        }
+    }
+
+    let mut groups: Vec<StatsEntry> = groups
+        .into_iter()
+        .map(|(name, (seconds, record_count))| StatsEntry {
+            name: name.to_string(),
+            seconds,
+            share: if total_seconds > 0 {
+                seconds as f64 / total_seconds as f64
+            } else {
+                0.0
+            },
+            record_count,
+        })
+        .collect();
+    groups.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.name.cmp(&b.name)));
+
+    let heatmap = dates
+        .iter()
+        .map(|date| {
+            let mut hours = vec![0; 24];
+            let mut top = vec![String::new(); 24];
+            for hour in 0..24u32 {
+                if let Some(cell) = cells.get(&(*date, hour)) {
+                    hours[hour as usize] = cell.values().sum();
+                    top[hour as usize] = cell
+                        .iter()
+                        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                        .map(|(name, _)| name.to_string())
+                        .unwrap_or_default();
+                }
+            }
+            HeatmapDay {
+                date: date.format("%Y-%m-%d").to_string(),
+                hours,
+                top,
+            }
+        })
+        .collect();
+
+    TimeStats {
+        start: range.start.format("%Y-%m-%d").to_string(),
+        end: range.end.format("%Y-%m-%d").to_string(),
+        group_by: group_by.as_str().to_string(),
+        total_seconds,
+        groups,
+        heatmap,
+    }
+}
+
+/// 把一段时间按整点切开，返回每个小时的起点和其中的秒数
+fn split_by_hour(from: NaiveDateTime, to: NaiveDateTime) -> Vec<(NaiveDateTime, i64)> {
+    let mut parts = Vec::new();
+    let mut cursor = from;
+    while cursor < to {
+        let hour_start = cursor
+            .date()
+            .and_hms_opt(cursor.hour(), 0, 0)
+            .unwrap_or(cursor);
+        let next_hour = (hour_start + Duration::hours(1)).min(to);
+        parts.push((hour_start, (next_hour - cursor).num_seconds()));
+        cursor = next_hour;
+    }
+    parts
+}
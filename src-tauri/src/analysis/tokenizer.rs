@@ -0,0 +1,269 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// 中英文混合文本分词
+///
+/// 英文单词、数字和代码标识符（含文件名）整体作为一个词；中文按内置词典正向最大匹配，
+/// 词典外的片段以虚词为界切开，较长的片段再按相邻两字切分
+pub struct Tokenizer;
+
+/// 词典中最长词的字数
+const MAX_WORD_LEN: usize = 4;
+
+/// 常见的中文词，覆盖屏幕记录里常出现的操作、界面和技术词汇
+const DICTIONARY: &[&str] = &[
+    // 操作
+    "编辑", "修改", "编写", "阅读", "浏览", "搜索", "查找", "查看", "调试", "运行", "测试", "部署",
+    "安装", "配置", "设置", "提交", "推送", "拉取", "合并", "下载", "上传", "登录", "注册", "打开",
+    "关闭", "保存", "删除", "创建", "新建", "复制", "粘贴", "输入", "导出", "导入", "编译", "构建",
+    "重构", "发布", "审查", "评审", "回复", "发送", "聊天", "会议", "讨论", "学习", "观看", "播放",
+    "记录", "整理", "翻译", "分析", "排查", "修复", "解决", "切换", "滚动", "点击", "选择", "填写",
+    "上线", "下线", "启动", "重启", "停止", "暂停", "恢复", "同步", "更新", "升级", "安装包",
+    // 界面
+    "窗口", "页面", "网页", "标签页", "侧边栏", "菜单", "按钮", "对话框", "弹窗", "输入框", "表格",
+    "文档", "文件", "文件夹", "目录", "项目", "仓库", "分支", "终端", "命令行", "控制台", "编辑器",
+    "浏览器", "设置页", "首页", "列表", "详情", "邮件", "消息", "群聊", "聊天记录", "日历", "笔记",
+    "幻灯片", "演示文稿", "表单", "图片", "视频", "截图", "链接", "评论", "通知",
+    // 技术
+    "代码", "函数", "方法", "变量", "接口", "类型", "参数", "返回值", "模块", "组件", "依赖", "配置文件",
+    "数据库", "服务器", "客户端", "前端", "后端", "中间件", "插件", "脚本", "日志", "报错", "错误",
+    "异常", "警告", "失败", "成功", "超时", "崩溃", "内存", "性能", "请求", "响应", "数据", "字段",
+    "测试用例", "单元测试", "文档注释", "注释", "编译器", "框架", "版本", "环境", "变量名", "路径",
+    "权限", "证书", "密码", "账号", "用户名", "网络", "代理", "端口", "进程", "线程", "容器", "镜像",
+    "算法", "需求", "任务", "缺陷", "工单", "代码审查", "合并请求", "拉取请求", "命令", "快捷键",
+    // 常见词
+    "用户", "正在", "当前", "进行", "一个", "一些", "没有", "什么", "哪些", "哪个", "怎么", "如何",
+    "这个", "那个", "已经", "然后", "显示", "屏幕", "画面", "内容", "界面", "使用", "应用", "程序",
+    "软件", "系统", "问题", "信息", "结果", "部分", "相关", "关于", "以及", "其中", "可能", "需要",
+    "中文", "英文", "官方", "教程", "示例", "说明", "步骤", "章节", "标题", "上方", "下方", "左侧",
+    "右侧", "中间", "顶部", "底部",
+    // 时间
+    "今天", "昨天", "前天", "刚才", "刚刚", "最近", "上午", "下午", "晚上", "本周", "这周", "上周",
+    "分钟", "小时", "时候", "之前", "之后",
+];
+
+/// 作为分界的单字虚词，不单独成词
+const STOP_CHARS: &[char] = &[
+    '的', '了', '在', '是', '和', '与', '及', '或', '并', '而', '把', '被', '对', '从', '向', '给',
+    '着', '过', '个', '这', '那', '也', '都', '就', '还', '又', '很', '将', '让', '于', '为', '其',
+    '之', '中', '里', '等', '地', '得', '上', '下', '我', '你', '他', '她', '它', '们', '吗', '呢',
+    '吧', '啊', '用',
+];
+
+/// 不作为关键词的常见词
+const STOP_WORDS: &[&str] = &[
+    // 英文
+    "the", "a", "an", "is", "are", "was", "were", "be", "been", "being", "have", "has", "had",
+    "do", "does", "did", "will", "would", "could", "should", "may", "might", "must", "shall",
+    "can", "need", "to", "of", "in", "for", "on", "with", "at", "by", "from", "as", "into",
+    "through", "during", "before", "after", "above", "below", "between", "under", "again",
+    "further", "then", "once", "here", "there", "when", "where", "why", "how", "all", "each",
+    "few", "more", "most", "other", "some", "such", "no", "nor", "not", "only", "own", "same",
+    "so", "than", "too", "very", "just", "and", "or", "but", "if", "it", "its", "this", "that",
+    "user", "using",
+    // 中文
+    "用户", "正在", "当前", "进行", "一个", "一些", "没有", "什么", "哪些", "哪个", "怎么", "如何",
+    "这个", "那个", "已经", "然后", "显示", "屏幕", "画面", "内容", "界面", "使用", "相关", "关于",
+    "以及", "其中", "可能", "需要", "部分", "信息", "上方", "下方", "左侧", "右侧", "中间", "顶部",
+    "底部", "今天", "昨天", "前天", "刚才", "刚刚", "最近", "上午", "下午", "晚上", "本周", "这周",
+    "上周", "分钟", "小时", "时候", "之前", "之后",
+];
+
+fn dictionary() -> &'static HashSet<&'static str> {
+    static DICT: OnceLock<HashSet<&'static str>> = OnceLock::new();
+    DICT.get_or_init(|| DICTIONARY.iter().copied().collect())
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F
+        | 0x3040..=0x30FF | 0xAC00..=0xD7AF)
+}
+
+/// 标识符中允许出现的字符：字母、数字、下划线，以及文件名、包名里的 `.`、`-`、`+`、`#`
+fn is_word_char(ch: char) -> bool {
+    (ch.is_alphanumeric() && !is_cjk(ch)) || matches!(ch, '_' | '.' | '-' | '+' | '#' | '$')
+}
+
+impl Tokenizer {
+    /// 分词，英文统一为小写；保留单字，供检索和相似度使用
+    pub fn tokenize(text: &str) -> Vec<String> {
+        Self::tokenize_raw(text).into_iter().map(|t| t.to_lowercase()).collect()
+    }
+
+    /// 分词，保留英文原有大小写（拆分 camelCase 时需要）
+    fn tokenize_raw(text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let chars: Vec<char> = text.chars().collect();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let ch = chars[i];
+            if is_cjk(ch) {
+                let start = i;
+                while i < chars.len() && is_cjk(chars[i]) {
+                    i += 1;
+                }
+                segment_cjk(&chars[start..i], &mut tokens);
+            } else if is_word_char(ch) {
+                let start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                // 句末标点和连字符不属于词
+                let word = word.trim_matches(|c| matches!(c, '.' | '-'));
+                if !word.is_empty() {
+                    tokens.push(word.to_string());
+                }
+            } else {
+                i += 1;
+            }
+        }
+        tokens
+    }
+
+    /// 检索和相似度比较用的词项：分词结果，加上中文词的相邻两字和标识符的组成部分
+    ///
+    /// 词典没收录的词切分可能不一致，补充两字组后 "聊天记录" 也能被 "聊天" 匹配
+    pub fn terms(text: &str) -> HashSet<String> {
+        let mut terms = HashSet::new();
+        for token in Self::tokenize_raw(text) {
+            let chars: Vec<char> = token.chars().collect();
+            let lower = token.to_lowercase();
+            if chars.iter().all(|c| is_cjk(*c)) {
+                for pair in chars.windows(2) {
+                    terms.insert(pair.iter().collect::<String>());
+                }
+            } else {
+                for part in split_identifier(&token) {
+                    if part.len() >= 2 && part != lower {
+                        terms.insert(part);
+                    }
+                }
+            }
+            terms.insert(lower);
+        }
+        terms
+    }
+
+    /// 两段文本的相似度（词项的 Jaccard 系数，0.0 - 1.0）
+    pub fn similarity(text1: &str, text2: &str) -> f64 {
+        let terms1 = Self::terms(text1);
+        let terms2 = Self::terms(text2);
+        if terms1.is_empty() && terms2.is_empty() {
+            return 1.0;
+        }
+        let intersection = terms1.intersection(&terms2).count();
+        let union = terms1.union(&terms2).count();
+        intersection as f64 / union as f64
+    }
+
+    /// `text` 的词项是否覆盖 `query` 的大部分词项（不少于一半，至少一个）
+    pub fn matches(text_terms: &HashSet<String>, query: &str) -> bool {
+        let query_terms = Self::terms(query);
+        if query_terms.is_empty() {
+            return false;
+        }
+        let hit = query_terms.iter().filter(|t| text_terms.contains(*t)).count();
+        hit * 2 >= query_terms.len() && hit > 0
+    }
+
+    /// 提取关键词：去掉停用词、单字和纯数字，按出现次数排序，次数相同按首次出现的顺序
+    pub fn keywords(text: &str, max_keywords: usize) -> Vec<String> {
+        let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+        for (position, token) in Self::tokenize(text).into_iter().enumerate() {
+            if !Self::is_keyword(&token) {
+                continue;
+            }
+            counts.entry(token).or_insert((0, position)).0 += 1;
+        }
+
+        let mut words: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+        words.sort_by_key(|(_, (count, first))| (std::cmp::Reverse(*count), *first));
+        words.into_iter().take(max_keywords).map(|(word, _)| word).collect()
+    }
+
+    /// 是否适合作为关键词
+    pub fn is_keyword(token: &str) -> bool {
+        let char_count = token.chars().count();
+        if char_count < 2 || STOP_WORDS.contains(&token) {
+            return false;
+        }
+        if token.chars().all(|c| c.is_ascii_digit() || c == '.') {
+            return false;
+        }
+        // 英文单词至少 3 个字母（保留 js、ts 这类扩展名会带来太多噪声）
+        !(token.is_ascii() && char_count < 3)
+    }
+}
+
+/// 中文片段：词典正向最大匹配；词典外的字以虚词为界合并，超过 4 个字的片段每两字切开
+fn segment_cjk(chars: &[char], tokens: &mut Vec<String>) {
+    let dict = dictionary();
+    let mut unknown: Vec<char> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let longest = (2..=MAX_WORD_LEN.min(chars.len() - i))
+            .rev()
+            .map(|len| chars[i..i + len].iter().collect::<String>())
+            .find(|word| dict.contains(word.as_str()));
+
+        if let Some(word) = longest {
+            flush_unknown(&mut unknown, tokens);
+            i += word.chars().count();
+            tokens.push(word);
+        } else if STOP_CHARS.contains(&chars[i]) {
+            flush_unknown(&mut unknown, tokens);
+            i += 1;
+        } else {
+            unknown.push(chars[i]);
+            i += 1;
+        }
+    }
+    flush_unknown(&mut unknown, tokens);
+}
+
+fn flush_unknown(unknown: &mut Vec<char>, tokens: &mut Vec<String>) {
+    if unknown.len() <= MAX_WORD_LEN {
+        if !unknown.is_empty() {
+            tokens.push(unknown.iter().collect());
+        }
+    } else {
+        // 奇数长度时最后三个字合为一词
+        let mut rest: &[char] = unknown;
+        while !rest.is_empty() {
+            let len = if rest.len() == 3 { 3 } else { 2.min(rest.len()) };
+            tokens.push(rest[..len].iter().collect());
+            rest = &rest[len..];
+        }
+    }
+    unknown.clear();
+}
+
+/// 拆分标识符：snake_case、kebab-case、camelCase 和文件名的各部分
+fn split_identifier(token: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+
+    for ch in token.chars() {
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if ch.is_uppercase() && prev_lower && !current.is_empty() {
+            parts.push(std::mem::take(&mut current));
+        }
+        prev_lower = ch.is_lowercase() || ch.is_ascii_digit();
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
@@ -25,7 +25,7 @@ impl ContextBuilder {
         for record in summaries.iter().rev().take(max_records) {
             context.push_str(&format!(
                 "- [{}] {} (应用: {}, 操作: {})\n",
-                &record.timestamp[11..19],
+                record.time_label(),
                 record.summary,
                 record.app,
                 record.action
@@ -1,3 +1,5 @@
+use crate::analysis::Tokenizer;
+
 /// 用户意图识别
 pub struct IntentRecognizer;
 
@@ -100,14 +102,12 @@ impl IntentRecognizer {
             }
         }
 
-        // 如果没有引号内容，提取名词性词汇
+        // 如果没有引号内容，分词后提取关键词
         if keywords.is_empty() {
-            let words: Vec<&str> = text.split_whitespace().collect();
-            for word in words {
-                if word.len() > 2 && !Self::is_stop_word(word) {
-                    keywords.push(word.to_string());
-                }
-            }
+            keywords = Tokenizer::keywords(text, 5)
+                .into_iter()
+                .filter(|word| !Self::is_stop_word(word))
+                .collect();
         }
 
         keywords
@@ -115,9 +115,7 @@ impl IntentRecognizer {
 
     fn is_stop_word(word: &str) -> bool {
         let stop_words = [
-            "的", "了", "是", "在", "我", "有", "和", "就", "不", "人",
-            "都", "一", "一个", "上", "也", "很", "到", "说", "要", "去",
-            "你", "会", "着", "没有", "看", "好", "自己", "这",
+            "搜索", "查找", "找找", "一下", "自己", "帮我",
         ];
         stop_words.contains(&word)
     }
@@ -0,0 +1,235 @@
+use crate::storage::{CaptureConfig, ChangeRegion};
+use image::DynamicImage;
+
+/// 每个分块差异哈希的采样尺寸（9x8 像素，相邻列比较得到 64 位）
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
+
+/// 帧指纹，用于和同一显示器的上一帧比较
+#[derive(Debug, Clone)]
+pub enum FrameFingerprint {
+    /// 整帧 8x8 均值哈希
+    Simple(u64),
+    /// 分块差异哈希
+    Tiles(TileHashes),
+}
+
+#[derive(Debug, Clone)]
+pub struct TileHashes {
+    columns: u32,
+    rows: u32,
+    width: u32,
+    height: u32,
+    hashes: Vec<u64>,
+}
+
+/// 比较结果
+pub struct ChangeResult {
+    pub changed: bool,
+    /// 变化分块的外接矩形（原图像素坐标）；简单模式下为 None
+    pub region: Option<ChangeRegion>,
+}
+
+/// 画面变化检测
+///
+/// - `simple`：整帧缩到 8x8 计算均值哈希，相似度低于 `change_threshold` 视为变化
+/// - `tiles`：把画面切成网格，每块计算差异哈希，任一分块（或加权比例的分块）变化即视为变化
+pub enum ChangeDetector {
+    Simple {
+        threshold: f32,
+    },
+    Tiles {
+        columns: u32,
+        rows: u32,
+        bit_threshold: u32,
+        change_ratio: f32,
+    },
+}
+
+impl ChangeDetector {
+    pub fn from_config(config: &CaptureConfig) -> Self {
+        match config.change_detection.as_str() {
+            "tiles" => ChangeDetector::Tiles {
+                columns: config.tile_columns.clamp(1, 64),
+                rows: config.tile_rows.clamp(1, 64),
+                bit_threshold: config.tile_bit_threshold.min(64),
+                change_ratio: config.tile_change_ratio.clamp(0.0, 1.0),
+            },
+            _ => ChangeDetector::Simple {
+                threshold: config.change_threshold,
+            },
+        }
+    }
+
+    pub fn fingerprint(&self, image: &DynamicImage) -> FrameFingerprint {
+        match self {
+            ChangeDetector::Simple { .. } => FrameFingerprint::Simple(compute_image_hash(image)),
+            ChangeDetector::Tiles { columns, rows, .. } => {
+                FrameFingerprint::Tiles(compute_tile_hashes(image, *columns, *rows))
+            }
+        }
+    }
+
+    pub fn compare(&self, prev: &FrameFingerprint, current: &FrameFingerprint) -> ChangeResult {
+        match (self, prev, current) {
+            (ChangeDetector::Simple { threshold }, FrameFingerprint::Simple(a), FrameFingerprint::Simple(b)) => {
+                ChangeResult {
+                    changed: hash_similarity(*a, *b) < *threshold,
+                    region: None,
+                }
+            }
+            (
+                ChangeDetector::Tiles { bit_threshold, change_ratio, .. },
+                FrameFingerprint::Tiles(a),
+                FrameFingerprint::Tiles(b),
+            ) if a.same_layout(b) => compare_tiles(a, b, *bit_threshold, *change_ratio),
+            // 模式或分辨率变了，无法比较，按整帧变化处理
+            _ => ChangeResult {
+                changed: true,
+                region: None,
+            },
+        }
+    }
+}
+
+impl TileHashes {
+    fn same_layout(&self, other: &TileHashes) -> bool {
+        self.columns == other.columns
+            && self.rows == other.rows
+            && self.width == other.width
+            && self.height == other.height
+    }
+
+    /// 分块在原图中的像素范围
+    fn tile_bounds(&self, column: u32, row: u32) -> (u32, u32, u32, u32) {
+        let x0 = column * self.width / self.columns;
+        let x1 = (column + 1) * self.width / self.columns;
+        let y0 = row * self.height / self.rows;
+        let y1 = (row + 1) * self.height / self.rows;
+        (x0, y0, x1, y1)
+    }
+}
+
+fn compare_tiles(prev: &TileHashes, current: &TileHashes, bit_threshold: u32, change_ratio: f32) -> ChangeResult {
+    let mut changed_tiles = 0usize;
+    let mut weighted = 0.0f32;
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for row in 0..current.rows {
+        for column in 0..current.columns {
+            let idx = (row * current.columns + column) as usize;
+            let distance = (prev.hashes[idx] ^ current.hashes[idx]).count_ones();
+            if distance <= bit_threshold {
+                continue;
+            }
+
+            changed_tiles += 1;
+            // 按变化位数加权，变化越大权重越高
+            weighted += distance as f32 / 64.0;
+
+            let (x0, y0, x1, y1) = current.tile_bounds(column, row);
+            bounds = Some(match bounds {
+                Some((bx0, by0, bx1, by1)) => (bx0.min(x0), by0.min(y0), bx1.max(x1), by1.max(y1)),
+                None => (x0, y0, x1, y1),
+            });
+        }
+    }
+
+    let total = current.hashes.len().max(1) as f32;
+    let changed = if change_ratio <= 0.0 {
+        changed_tiles > 0
+    } else {
+        weighted / total >= change_ratio
+    };
+
+    ChangeResult {
+        changed,
+        region: bounds.map(|(x0, y0, x1, y1)| ChangeRegion {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+        }),
+    }
+}
+
+/// 一次性缩小整帧，每个分块对应 9x8 个采样点，逐块计算差异哈希
+fn compute_tile_hashes(image: &DynamicImage, columns: u32, rows: u32) -> TileHashes {
+    let gray = image
+        .thumbnail_exact(columns * DHASH_WIDTH, rows * DHASH_HEIGHT)
+        .to_luma8();
+
+    let mut hashes = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let mut hash: u64 = 0;
+            let mut bit = 0;
+            for y in 0..DHASH_HEIGHT {
+                for x in 0..DHASH_WIDTH - 1 {
+                    let px = column * DHASH_WIDTH + x;
+                    let py = row * DHASH_HEIGHT + y;
+                    let left = gray.get_pixel(px, py).0[0];
+                    let right = gray.get_pixel(px + 1, py).0[0];
+                    if left > right {
+                        hash |= 1 << bit;
+                    }
+                    bit += 1;
+                }
+            }
+            hashes.push(hash);
+        }
+    }
+
+    TileHashes {
+        columns,
+        rows,
+        width: image.width(),
+        height: image.height(),
+        hashes,
+    }
+}
+
+/// 截图内容指纹：缩小并略微降低灰度精度后做 FNV-1a 哈希，几乎相同的画面得到相同的指纹
+///
+/// 缩略图保留足够分辨率，新增一行文字这类小变化仍会得到不同的指纹
+pub fn content_key(image: &DynamicImage) -> String {
+    let gray = image.thumbnail_exact(320, 180).to_luma8();
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in [image.width(), image.height()]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .chain(gray.pixels().map(|p| p.0[0] >> 2))
+    {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("{:016x}", hash)
+}
+
+/// 计算图像的简单哈希值（用于快速对比）
+fn compute_image_hash(image: &DynamicImage) -> u64 {
+    // 缩小图像到8x8进行快速哈希
+    let small = image.resize_exact(8, 8, image::imageops::FilterType::Nearest);
+    let gray = small.to_luma8();
+
+    let pixels: Vec<u8> = gray.pixels().map(|p| p.0[0]).collect();
+    let avg: u64 = pixels.iter().map(|&p| p as u64).sum::<u64>() / pixels.len() as u64;
+
+    // 生成感知哈希
+    let mut hash: u64 = 0;
+    for (i, &pixel) in pixels.iter().enumerate() {
+        if pixel as u64 > avg {
+            hash |= 1 << i;
+        }
+    }
+    hash
+}
+
+/// 计算两个哈希的相似度 (0.0 - 1.0)
+fn hash_similarity(hash1: u64, hash2: u64) -> f32 {
+    let xor = hash1 ^ hash2;
+    let diff_bits = xor.count_ones();
+    1.0 - (diff_bits as f32 / 64.0)
+}
@@ -1,39 +1,162 @@
+mod change;
+mod ocr;
+mod pipeline;
+mod privacy;
+mod redact;
+mod schedule;
 mod screen;
 mod scheduler;
-
+mod sink;
+mod source;
+mod telemetry;
+
+pub use change::*;
+pub use ocr::*;
+pub use privacy::*;
+pub use redact::*;
+pub use schedule::*;
 pub use screen::*;
 pub use scheduler::*;
+pub use sink::*;
+pub use source::*;
+pub use telemetry::*;
+
+use pipeline::{CommitSequencer, FrameQueue, PendingFrame};
 
+use crate::analysis::{ActivityRules, InfoExtractor, IssueInfo, IssueParser, Scrubber};
 use crate::model::{build_model_error_alert, ModelManager};
-use crate::storage::{Config, StorageManager, SummaryRecord};
+use crate::storage::{CaptureConfig, Config, SpanPolicy, StorageManager, SummaryRecord};
 use chrono::{DateTime, Duration, Local};
 use image::DynamicImage;
 use parking_lot::Mutex as ParkingMutex;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter};
-use tokio::sync::mpsc;
+use std::time::{Instant, SystemTime};
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
 
 const RECENT_CONTEXT_MINUTES: i64 = 3;
+/// 每条记录保留的关键词数
+const RECORD_KEYWORD_LIMIT: usize = 8;
+/// 暂停或不在监控时段时，检查是否恢复的间隔
+const IDLE_POLL: std::time::Duration = std::time::Duration::from_secs(30);
+/// 提示词中附带的屏幕文字最大字符数（每帧）
+const OCR_PROMPT_CHARS: usize = 1500;
+/// 一次请求最多合并的帧数，图片过多时单次请求过大、耗时过长
+const MAX_ANALYSIS_BATCH: usize = 8;
+/// 画面没有变化时更新记录 `last_seen` 的最小间隔（秒），避免每一帧都改写记录文件
+const SEEN_INTERVAL_SECONDS: i64 = 60;
+/// 即时提问的画面命中隐私规则时代替模型回答返回的提示
+const PRIVATE_ANSWER: &str = "当前画面命中隐私规则，已屏蔽，不显示关于该画面的回答；冷却期内不会上传画面，可以直接提问，从历史记录中查询";
+/// 推送 `capture-status` 事件的间隔
+const STATUS_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+pub const CAPTURE_STATUS_EVENT: &str = "capture-status";
 
 pub struct CaptureManager {
     is_running: Arc<ParkingMutex<bool>>,
-    record_count: Arc<ParkingMutex<u64>>,
-    skip_count: Arc<ParkingMutex<u64>>,  // 跳过的帧数
+    telemetry: Arc<ParkingMutex<CaptureTelemetry>>,
     stop_tx: Option<mpsc::Sender<()>>,
+    session: SessionState,
+    /// 当前会话的上下文，用于运行中更新配置
+    context: Option<Arc<AnalysisContext>>,
+    /// 当前会话的后台任务，结束时会话已全部提交
+    task: Option<JoinHandle<()>>,
+}
+
+/// 采集过程中跨帧共享的状态
+#[derive(Clone)]
+struct SessionState {
     recent_alerts: Arc<ParkingMutex<HashMap<String, DateTime<Local>>>>,
     last_issue_key: Arc<ParkingMutex<Option<String>>>,
+    /// 命中隐私规则后，在此时间之前不再上传画面
+    privacy_pause_until: Arc<ParkingMutex<Option<DateTime<Local>>>>,
+    /// 手动暂停状态
+    manual_pause: Arc<ParkingMutex<Option<ManualPause>>>,
+    /// 采集循环当前的空闲原因，None 表示正在采集
+    idle: Arc<ParkingMutex<Option<IdleReason>>>,
+    /// 暂停/恢复时唤醒采集循环
+    wake: Arc<Notify>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ManualPause {
+    /// 自动恢复时间，None 表示需要手动恢复
+    resume_at: Option<DateTime<Local>>,
+}
+
+/// 采集循环空闲（不截屏）的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleReason {
+    /// 用户手动暂停
+    Paused,
+    /// 不在配置的监控时段内
+    OffSchedule,
+}
+
+impl IdleReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdleReason::Paused => "paused",
+            IdleReason::OffSchedule => "off_schedule",
+        }
+    }
+}
+
+impl SessionState {
+    fn new() -> Self {
+        Self {
+            recent_alerts: Arc::new(ParkingMutex::new(HashMap::new())),
+            last_issue_key: Arc::new(ParkingMutex::new(None)),
+            privacy_pause_until: Arc::new(ParkingMutex::new(None)),
+            manual_pause: Arc::new(ParkingMutex::new(None)),
+            idle: Arc::new(ParkingMutex::new(None)),
+            wake: Arc::new(Notify::new()),
+        }
+    }
+
+    /// 是否处于隐私冷却期
+    fn privacy_paused(&self, at: DateTime<Local>) -> bool {
+        self.privacy_pause_until(at).is_some()
+    }
+
+    /// 命中隐私规则：从 `at` 起暂停上传画面
+    fn start_privacy_pause(&self, at: DateTime<Local>, config: &CaptureConfig) {
+        let cooldown = Duration::seconds(config.privacy_cooldown_seconds as i64);
+        *self.privacy_pause_until.lock() = Some(at + cooldown);
+    }
+
+    /// 处于隐私冷却期时返回结束时间
+    fn privacy_pause_until(&self, at: DateTime<Local>) -> Option<DateTime<Local>> {
+        (*self.privacy_pause_until.lock()).filter(|until| at < *until)
+    }
+
+    /// 判断当前是否应该空闲；手动暂停到期时自动清除
+    fn idle_reason(&self, schedule: &CaptureSchedule, now: DateTime<Local>) -> Option<IdleReason> {
+        let mut pause = self.manual_pause.lock();
+        if let Some(current) = *pause {
+            match current.resume_at {
+                Some(resume_at) if now >= resume_at => *pause = None,
+                _ => return Some(IdleReason::Paused),
+            }
+        }
+
+        if !schedule.is_active(&now) {
+            return Some(IdleReason::OffSchedule);
+        }
+        None
+    }
 }
 
 impl CaptureManager {
     pub fn new() -> Self {
         Self {
             is_running: Arc::new(ParkingMutex::new(false)),
-            record_count: Arc::new(ParkingMutex::new(0)),
-            skip_count: Arc::new(ParkingMutex::new(0)),
+            telemetry: Arc::new(ParkingMutex::new(CaptureTelemetry::default())),
             stop_tx: None,
-            recent_alerts: Arc::new(ParkingMutex::new(HashMap::new())),
-            last_issue_key: Arc::new(ParkingMutex::new(None)),
+            session: SessionState::new(),
+            context: None,
+            task: None,
         }
     }
 
@@ -41,15 +164,68 @@ impl CaptureManager {
         *self.is_running.lock()
     }
 
-    pub fn get_count(&self) -> u64 {
-        *self.record_count.lock()
+    /// 当前采集状态和运行指标
+    pub fn status(&self) -> CaptureStatus {
+        build_status(&self.is_running, &self.session, &self.telemetry)
+    }
+
+    /// 暂停采集，保留会话状态和计数；`minutes` 大于 0 时到期自动恢复
+    pub fn pause(&self, minutes: Option<u64>) -> Result<(), String> {
+        if !self.is_running() {
+            return Err("监控未运行".to_string());
+        }
+
+        let resume_at = minutes
+            .filter(|m| *m > 0)
+            .map(|m| Local::now() + Duration::minutes(m as i64));
+        *self.session.manual_pause.lock() = Some(ManualPause { resume_at });
+        self.session.wake.notify_one();
+        Ok(())
+    }
+
+    /// 恢复手动暂停的采集（不在监控时段内时仍保持空闲）
+    pub fn resume(&self) -> Result<(), String> {
+        if !self.is_running() {
+            return Err("监控未运行".to_string());
+        }
+
+        *self.session.manual_pause.lock() = None;
+        self.session.wake.notify_one();
+        Ok(())
+    }
+
+    /// 即时提问用到的会话状态
+    pub fn ask_session(&self) -> ScreenAskSession {
+        ScreenAskSession {
+            session: self.session.clone(),
+            context: self.context.clone().filter(|_| self.is_running()),
+        }
+    }
+
+    /// 把新配置应用到正在运行的会话，不需要重启监控，计数和上一帧指纹保留
+    pub fn update_config(&self, config: Config) {
+        if !self.is_running() {
+            return;
+        }
+        if let Some(context) = &self.context {
+            context.apply_config(config);
+        }
     }
 
-    pub fn get_skip_count(&self) -> u64 {
-        *self.skip_count.lock()
+    pub async fn start(&mut self, config: Config, events: Arc<dyn EventSink>) {
+        self.start_with_source(config, events, Box::new(ScreenSource)).await;
     }
 
-    pub async fn start(&mut self, config: Config, app_handle: AppHandle) {
+    /// 使用指定的画面来源启动（实时截屏或目录回放）
+    ///
+    /// 截屏任务按间隔截屏并做变化检测，需要分析的帧放入有界队列；
+    /// `analysis_concurrency` 个分析任务从队列取帧调用模型，结果按截屏顺序写入存储
+    pub async fn start_with_source(
+        &mut self,
+        config: Config,
+        events: Arc<dyn EventSink>,
+        mut source: Box<dyn FrameSource>,
+    ) {
         if self.is_running() {
             return;
         }
@@ -57,170 +233,611 @@ impl CaptureManager {
         let (stop_tx, mut stop_rx) = mpsc::channel::<()>(1);
         self.stop_tx = Some(stop_tx);
 
-        let is_running = self.is_running.clone();
-        let record_count = self.record_count.clone();
-        let skip_count = self.skip_count.clone();
-        let recent_alerts = self.recent_alerts.clone();
-        let last_issue_key = self.last_issue_key.clone();
-        let interval_ms = config.capture.interval_ms;
+        *self.is_running.lock() = true;
+
+        let storage_manager = StorageManager::new();
+        let context = Arc::new(AnalysisContext {
+            model_manager: ModelManager::new(),
+            config_modified: ParkingMutex::new(storage_manager.config_modified_time()),
+            rules: ParkingMutex::new(Arc::new(load_activity_rules(&storage_manager))),
+            rules_modified: ParkingMutex::new(storage_manager.activity_rules_modified_time()),
+            storage_manager,
+            session: self.session.clone(),
+            events,
+            scheduler: ParkingMutex::new(CaptureScheduler::from_config(&config.capture)),
+            queue: FrameQueue::new(queue_capacity(&config.capture)),
+            sequencer: ParkingMutex::new(CommitSequencer::new()),
+            workers: ParkingMutex::new(Vec::new()),
+            active_workers: AtomicUsize::new(0),
+            next_seq: AtomicU64::new(0),
+            is_running: self.is_running.clone(),
+            telemetry: self.telemetry.clone(),
+            settings: ParkingMutex::new(Arc::new(LiveSettings::new(config))),
+        });
+        self.context = Some(context.clone());
 
-        *is_running.lock() = true;
+        self.task = Some(tokio::spawn(async move {
+            let ticker = tokio::spawn(emit_status_periodically(context.clone()));
+            context.ensure_workers();
 
-        tokio::spawn(async move {
-            let model_manager = ModelManager::new();
-            let storage_manager = StorageManager::new();
-            let mut interval = tokio::time::interval(
-                tokio::time::Duration::from_millis(interval_ms)
-            );
+            let stopped = run_capture_producer(&context, source.as_mut(), &mut stop_rx).await;
 
-            // 上一帧的图像哈希（用于对比）
-            let mut prev_image_hash: Option<u64> = None;
+            // 用户停止时丢弃还没开始分析的帧，正在进行的分析完成后照常提交
+            if stopped {
+                for pending in context.queue.clear() {
+                    context.telemetry.lock().record_skipped(1);
+                    context.finish(pending.seq, None);
+                }
+            }
+            context.queue.close();
+            // 等待期间更新配置可能又补了分析任务，直到全部退出
             loop {
-                tokio::select! {
-                    _ = interval.tick() => {
-                        if !*is_running.lock() {
-                            break;
-                        }
-
-                        // 执行截屏和识别
-                        match capture_and_analyze_with_diff(
-                            &config,
-                            &model_manager,
-                            &storage_manager,
-                            &recent_alerts,
-                            &last_issue_key,
-                            &app_handle,
-                            &mut prev_image_hash,
-                        ).await {
-                            Ok(analyzed) => {
-                                if analyzed {
-                                    *record_count.lock() += 1;
-                                } else {
-                                    *skip_count.lock() += 1;
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("截屏分析失败: {}", e);
-                            }
-                        }
-
-                    }
-                    _ = stop_rx.recv() => {
-                        break;
-                    }
+                let workers = std::mem::take(&mut *context.workers.lock());
+                if workers.is_empty() {
+                    break;
+                }
+                for worker in workers {
+                    let _ = worker.await;
                 }
             }
 
-            *is_running.lock() = false;
-        });
+            // 用户停止时 stop() 已经更新了状态，这里不再改动，避免影响随后新启动的会话
+            if !stopped {
+                *context.is_running.lock() = false;
+                *context.session.idle.lock() = None;
+            }
+
+            ticker.abort();
+            let _ = emit_event(context.events.as_ref(), CAPTURE_STATUS_EVENT, context.status());
+        }));
+    }
+
+    /// 等待当前会话结束（画面来源耗尽或被停止），已排队的分析全部提交后返回
+    pub async fn wait(&mut self) {
+        if let Some(task) = self.task.as_mut() {
+            let _ = task.await;
+            self.task = None;
+        }
     }
 
     pub async fn stop(&mut self) {
         *self.is_running.lock() = false;
+        *self.session.manual_pause.lock() = None;
+        *self.session.idle.lock() = None;
+        self.context = None;
         if let Some(tx) = self.stop_tx.take() {
             let _ = tx.send(()).await;
         }
     }
 }
 
-/// 计算图像的简单哈希值（用于快速对比）
-fn compute_image_hash(image: &DynamicImage) -> u64 {
-    // 缩小图像到8x8进行快速哈希
-    let small = image.resize_exact(8, 8, image::imageops::FilterType::Nearest);
-    let gray = small.to_luma8();
-
-    let pixels: Vec<u8> = gray.pixels().map(|p| p.0[0]).collect();
-    let avg: u64 = pixels.iter().map(|&p| p as u64).sum::<u64>() / pixels.len() as u64;
+/// 运行中可以替换的配置，以及由配置生成的隐私规则、脱敏规则和文字识别引擎
+struct LiveSettings {
+    config: Config,
+    privacy: PrivacyFilter,
+    scrubber: Scrubber,
+    ocr: Arc<dyn OcrEngine>,
+}
 
-    // 生成感知哈希
-    let mut hash: u64 = 0;
-    for (i, &pixel) in pixels.iter().enumerate() {
-        if pixel as u64 > avg {
-            hash |= 1 << i;
+impl LiveSettings {
+    fn new(config: Config) -> Self {
+        Self {
+            privacy: PrivacyFilter::from_config(&config.capture),
+            scrubber: Scrubber::from_config(&config.capture),
+            ocr: ocr_from_config(&config.capture),
+            config,
         }
     }
-    hash
 }
 
-fn save_screenshot(
-    storage_manager: &StorageManager,
-    image: &DynamicImage,
-    now: &DateTime<Local>,
-    quality: u8,
-) -> Option<String> {
-    let dir = match storage_manager.screenshots_dir() {
-        Ok(dir) => dir,
-        Err(err) => {
-            eprintln!("获取截图目录失败: {}", err);
-            return None;
+/// 截屏任务和分析任务共享的上下文
+struct AnalysisContext {
+    /// 当前配置；每轮截屏和每次分析开始时取一份快照，更新配置时整体替换
+    settings: ParkingMutex<Arc<LiveSettings>>,
+    /// 最近一次应用的 config.json 修改时间
+    config_modified: ParkingMutex<Option<SystemTime>>,
+    /// 自定义活动分类规则，activity_rules.json 修改后整体替换
+    rules: ParkingMutex<Arc<ActivityRules>>,
+    rules_modified: ParkingMutex<Option<SystemTime>>,
+    model_manager: ModelManager,
+    storage_manager: StorageManager,
+    session: SessionState,
+    events: Arc<dyn EventSink>,
+    scheduler: ParkingMutex<CaptureScheduler>,
+    queue: FrameQueue,
+    sequencer: ParkingMutex<CommitSequencer<Commit>>,
+    workers: ParkingMutex<Vec<JoinHandle<()>>>,
+    /// 正在运行的分析任务数
+    active_workers: AtomicUsize,
+    /// 下一帧的提交序号；截屏任务和即时提问共用，记录按序号顺序写入
+    next_seq: AtomicU64,
+    is_running: Arc<ParkingMutex<bool>>,
+    telemetry: Arc<ParkingMutex<CaptureTelemetry>>,
+}
+
+impl AnalysisContext {
+    fn status(&self) -> CaptureStatus {
+        build_status(&self.is_running, &self.session, &self.telemetry)
+    }
+
+    fn settings(&self) -> Arc<LiveSettings> {
+        self.settings.lock().clone()
+    }
+
+    /// 应用新配置：间隔、变化检测、隐私规则、模型等从下一帧起生效
+    ///
+    /// 计数、上一帧指纹和已观测到的模型耗时都保留；正在进行的分析仍使用旧配置完成
+    fn apply_config(self: &Arc<Self>, config: Config) {
+        self.scheduler.lock().reconfigure(&config.capture);
+        self.queue.set_capacity(queue_capacity(&config.capture));
+        *self.settings.lock() = Arc::new(LiveSettings::new(config));
+        *self.config_modified.lock() = self.storage_manager.config_modified_time();
+        self.ensure_workers();
+        // 空闲中的截屏任务立即按新的监控时段重新判断
+        self.session.wake.notify_one();
+    }
+
+    /// config.json 在外部被修改（手动编辑或其他窗口保存）时重新加载
+    fn reload_config_if_modified(self: &Arc<Self>) {
+        let modified = self.storage_manager.config_modified_time();
+        if modified.is_none() || *self.config_modified.lock() == modified {
+            return;
         }
-    };
 
-    let filename = format!("{}.jpg", now.format("%Y%m%d-%H%M%S-%.3f"));
-    let path = dir.join(&filename);
-    let path_str = path.to_string_lossy();
+        match self.storage_manager.load_config() {
+            Ok(config) => self.apply_config(config),
+            Err(err) => {
+                // 编辑到一半的文件可能暂时无法解析，记下时间避免反复报错，下次保存时再试
+                *self.config_modified.lock() = modified;
+                eprintln!("重新加载配置失败: {}", err);
+            }
+        }
+    }
 
-    if let Err(err) = ScreenCapture::save_to_file(image, path_str.as_ref(), quality) {
-        eprintln!("保存截图失败: {}", err);
-        return None;
+    fn rules(&self) -> Arc<ActivityRules> {
+        self.rules.lock().clone()
+    }
+
+    /// activity_rules.json 新建、修改或删除后重新加载，从下一条记录起生效
+    fn reload_rules_if_modified(&self) {
+        let modified = self.storage_manager.activity_rules_modified_time();
+        if *self.rules_modified.lock() == modified {
+            return;
+        }
+        *self.rules_modified.lock() = modified;
+
+        match ActivityRules::load(&self.storage_manager.activity_rules_path()) {
+            Ok(rules) => *self.rules.lock() = Arc::new(rules),
+            // 解析失败时保留原有规则
+            Err(err) => eprintln!("重新加载分类规则失败: {}", err),
+        }
     }
 
-    Some(filename)
+    /// 分析任务数少于 `analysis_concurrency` 时补足；多出的任务处理完手上的帧后自行退出
+    fn ensure_workers(self: &Arc<Self>) {
+        let target = self.settings().config.capture.analysis_concurrency.max(1);
+        let mut workers = self.workers.lock();
+        while self.active_workers.load(Ordering::SeqCst) < target {
+            self.active_workers.fetch_add(1, Ordering::SeqCst);
+            workers.push(tokio::spawn(run_analysis_worker(self.clone())));
+        }
+    }
+
+    /// 并发数调小后，让多出的分析任务退出；返回 true 表示当前任务应退出
+    fn retire_worker(&self) -> bool {
+        let target = self.settings().config.capture.analysis_concurrency.max(1);
+        self.active_workers
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                if active > target {
+                    Some(active - 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    /// 分配下一帧的提交序号；分配后必须调用 `finish`，否则后面的记录会一直等待
+    fn reserve_seq(&self) -> u64 {
+        self.next_seq.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// 标记一帧处理完成（None 表示失败或被丢弃），并按截屏顺序提交已就绪的结果
+    fn finish(&self, seq: u64, commit: Option<FrameCommit>) {
+        self.complete(seq, commit.map(|commit| Commit::Frame(Box::new(commit))));
+    }
+
+    /// 画面没有变化：排在已经入队的帧之后，延后该显示器最近一条记录的 `last_seen`
+    fn mark_seen(&self, display_id: &str, at: DateTime<Local>) {
+        let seen = Commit::Seen {
+            display_id: display_id.to_string(),
+            at: at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        };
+        self.complete(self.reserve_seq(), Some(seen));
+    }
+
+    fn complete(&self, seq: u64, commit: Option<Commit>) {
+        // 持锁提交，保证不同分析任务的记录不会交错写入
+        let mut sequencer = self.sequencer.lock();
+        for commit in sequencer.complete(seq, commit) {
+            match commit {
+                Commit::Frame(commit) => self.commit(*commit),
+                Commit::Seen { display_id, at } => {
+                    if let Err(err) = self.storage_manager.mark_seen(&display_id, &at) {
+                        eprintln!("更新记录失败: {}", err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn commit(&self, commit: FrameCommit) {
+        let merge = SpanPolicy::from_config(&self.settings().config.storage);
+        if let Err(err) = self.storage_manager.save_summary_merged(&commit.record, merge.as_ref()) {
+            eprintln!("保存记录失败: {}", err);
+            self.telemetry.lock().record_failure(&err);
+            return;
+        }
+        self.telemetry.lock().record_analyzed();
+
+        // 如果检测到困难，主动推送提示
+        if let Some(pending) = commit.alert {
+            let alert_message = pending.alert;
+
+            let mut alert_log = String::new();
+            alert_log.push_str(&format!("time: {}\n", alert_message.timestamp));
+            alert_log.push_str(&format!("issue_type: {}\n", alert_message.issue_type));
+            alert_log.push_str(&format!("message: {}\n", alert_message.message));
+            if !alert_message.suggestion.is_empty() {
+                alert_log.push_str(&format!("suggestion: {}\n", alert_message.suggestion));
+            }
+            alert_log.push_str(&format!(
+                "confidence: {:.2}\nthreshold: {:.2}\n",
+                pending.confidence, pending.threshold
+            ));
+            if let Err(err) = self.storage_manager.write_log_snapshot("assistant-alert", &alert_log) {
+                eprintln!("写入提醒日志失败: {}", err);
+            }
+
+            if let Err(err) = emit_event(self.events.as_ref(), "assistant-alert", alert_message) {
+                eprintln!("发送提醒失败: {}", err);
+            }
+        }
+    }
 }
 
-/// 计算两个哈希的相似度 (0.0 - 1.0)
-fn hash_similarity(hash1: u64, hash2: u64) -> f32 {
-    let xor = hash1 ^ hash2;
-    let diff_bits = xor.count_ones();
-    1.0 - (diff_bits as f32 / 64.0)
+/// 截屏任务：按间隔截屏、遮挡、变化检测，把需要分析的帧放入队列
+///
+/// 返回 true 表示被用户停止，false 表示画面来源已耗尽
+async fn run_capture_producer(
+    context: &Arc<AnalysisContext>,
+    source: &mut dyn FrameSource,
+    stop_rx: &mut mpsc::Receiver<()>,
+) -> bool {
+    let session = &context.session;
+    let mut last_cycle: Option<Instant> = None;
+
+    // 每个显示器上一帧的指纹（用于对比）
+    let mut prev_fingerprints: HashMap<String, FrameFingerprint> = HashMap::new();
+    // 每个显示器最近一次入队分析或更新 last_seen 的截屏时间
+    let mut last_seen: HashMap<String, DateTime<Local>> = HashMap::new();
+    loop {
+        // 实时截屏按间隔等待；回放由来源自己控制节奏，队列满时等待分析跟上而不是丢帧
+        if source.self_paced() {
+            tokio::select! {
+                _ = context.queue.wait_for_space() => {}
+                _ = stop_rx.recv() => return true,
+            }
+        } else if let Some(started) = last_cycle {
+            let delay = context.scheduler.lock().delay_after(started.elapsed());
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = stop_rx.recv() => return true,
+            }
+        }
+        last_cycle = Some(Instant::now());
+
+        if !*context.is_running.lock() {
+            return true;
+        }
+
+        // 每轮使用最新的配置，运行中修改的设置从这一轮起生效
+        context.reload_config_if_modified();
+        context.reload_rules_if_modified();
+        let settings = context.settings();
+        let capture_config = &settings.config.capture;
+        let detector = ChangeDetector::from_config(capture_config);
+        // 回放的是历史截图，不受监控时段限制
+        let schedule = if source.self_paced() {
+            CaptureSchedule::default()
+        } else {
+            CaptureSchedule::from_config(capture_config)
+        };
+
+        // 暂停或不在监控时段：记录状态变化，等待恢复
+        let now = Local::now();
+        let idle = session.idle_reason(&schedule, now);
+        let previous_idle = std::mem::replace(&mut *session.idle.lock(), idle);
+        if idle != previous_idle {
+            let resume_at = session.manual_pause.lock().and_then(|pause| pause.resume_at);
+            if let Err(e) = record_idle_change(&context.storage_manager, now, idle, resume_at) {
+                eprintln!("记录暂停状态失败: {}", e);
+            }
+        }
+        if idle.is_some() {
+            tokio::select! {
+                _ = tokio::time::sleep(IDLE_POLL) => {}
+                _ = session.wake.notified() => {}
+                _ = stop_rx.recv() => return true,
+            }
+            // 恢复后立即截屏
+            last_cycle = None;
+            continue;
+        }
+
+        let frames = tokio::select! {
+            result = source.next_frames(capture_config) => result,
+            _ = stop_rx.recv() => return true,
+        };
+
+        let frames = match frames {
+            Ok(Some(frames)) => frames,
+            Ok(None) => return false,  // 回放结束
+            Err(e) => {
+                eprintln!("截屏失败: {}", e);
+                context.telemetry.lock().record_error(&format!("截屏失败: {}", e));
+                continue;
+            }
+        };
+
+        let mut queued = 0;
+        let mut skipped = 0;
+        for mut frame in frames {
+            // 先遮挡固定区域，后续的变化检测、上传和保存都基于遮挡后的画面
+            apply_redactions(&mut frame.image, &frame.display_id, &capture_config.redaction_regions);
+
+            // 如果启用了跳过无变化，进行对比
+            let change = if capture_config.skip_unchanged {
+                detect_change(&detector, &frame, &mut prev_fingerprints)
+            } else {
+                ChangeResult {
+                    changed: true,
+                    region: None,
+                }
+            };
+
+            // 处于隐私冷却期，跳过这一帧，这段时间不算在任何记录上
+            if session.privacy_paused(frame.captured_at) {
+                skipped += 1;
+                continue;
+            }
+            // 没有检测到变化，跳过这一帧，按间隔延后上一条记录的 last_seen，用时统计仍算在这条记录上
+            if !change.changed {
+                skipped += 1;
+                let due = last_seen
+                    .get(&frame.display_id)
+                    .map(|at| frame.captured_at - *at >= Duration::seconds(SEEN_INTERVAL_SECONDS))
+                    .unwrap_or(true);
+                if due {
+                    context.mark_seen(&frame.display_id, frame.captured_at);
+                    last_seen.insert(frame.display_id.clone(), frame.captured_at);
+                }
+                continue;
+            }
+            last_seen.insert(frame.display_id.clone(), frame.captured_at);
+
+            let pending = PendingFrame {
+                seq: context.reserve_seq(),
+                frame,
+                change_region: change.region,
+                screen_text: String::new(),
+            };
+            queued += 1;
+
+            // 队列已满：丢弃最旧的帧，保留最新画面
+            for dropped in context.queue.push(pending) {
+                skipped += 1;
+                context.finish(dropped.seq, None);
+            }
+        }
+
+        let interval_ms = {
+            let mut scheduler = context.scheduler.lock();
+            scheduler.record_capture(queued > 0);
+            scheduler.current_interval_ms()
+        };
+        let mut telemetry = context.telemetry.lock();
+        telemetry.record_capture(interval_ms);
+        telemetry.record_skipped(skipped);
+    }
 }
 
-/// 截屏并分析，支持跳过无变化的帧
-async fn capture_and_analyze_with_diff(
-    config: &Config,
-    model_manager: &ModelManager,
-    storage_manager: &StorageManager,
-    recent_alerts: &Arc<ParkingMutex<HashMap<String, DateTime<Local>>>>,
-    last_issue_key: &Arc<ParkingMutex<Option<String>>>,
-    app_handle: &AppHandle,
-    prev_hash: &mut Option<u64>,
-) -> Result<bool, String> {
-    // 1. 截屏
-    let image = ScreenCapture::capture_primary()?;
-    let now = Local::now();
-    let screenshot_ref = save_screenshot(storage_manager, &image, &now, config.capture.compress_quality);
-
-    // 2. 如果启用了跳过无变化，进行对比
-    if config.capture.skip_unchanged {
-        let current_hash = compute_image_hash(&image);
-
-        if let Some(prev) = *prev_hash {
-            let similarity = hash_similarity(prev, current_hash);
-
-            // 如果相似度超过阈值，跳过这一帧
-            if similarity >= config.capture.change_threshold {
-                return Ok(false);  // 返回false表示跳过
+/// 分析任务：从队列取帧调用模型，结果交给 sequencer 按顺序提交
+///
+/// 启用批量分析时，把已经在排队的帧（最多 `analysis_batch_size` 帧）合并为一次请求
+async fn run_analysis_worker(context: Arc<AnalysisContext>) {
+    loop {
+        if context.retire_worker() {
+            return;
+        }
+        let first = match context.queue.pop().await {
+            Some(first) => first,
+            None => break,
+        };
+
+        let batch_size = analysis_batch_size(&context.settings().config.capture);
+        let mut batch = vec![first];
+        batch.extend(context.queue.try_pop_many(batch_size - 1));
+
+        // 排队期间其他帧命中了隐私规则：冷却期内不再上传，已排队的帧直接丢弃
+        if context.session.privacy_paused(Local::now()) {
+            context.telemetry.lock().record_skipped(batch.len() as u64);
+            for pending in batch {
+                context.finish(pending.seq, None);
             }
+            continue;
         }
 
-        // 更新上一帧哈希
-        *prev_hash = Some(current_hash);
+        let results = if batch.len() == 1 {
+            let pending = batch.pop().expect("批次中至少有一帧");
+            let seq = pending.seq;
+            vec![(seq, analyze_frame(&context, pending).await)]
+        } else {
+            analyze_batch(&context, batch).await
+        };
+
+        for (seq, result) in results {
+            let commit = match result {
+                Ok(commit) => {
+                    if let Some(latency) = commit.latency {
+                        context
+                            .scheduler
+                            .lock()
+                            .record_analysis(latency, commit.record.has_issue);
+                        context.telemetry.lock().record_latency(latency);
+                    }
+                    Some(commit)
+                }
+                Err(err) => {
+                    eprintln!("截屏分析失败: {}", err);
+                    context.telemetry.lock().record_failure(&err);
+                    None
+                }
+            };
+            context.finish(seq, commit);
+        }
     }
+    context.active_workers.fetch_sub(1, Ordering::SeqCst);
+}
 
-    // 3. 转换为 base64
-    let image_base64 = ScreenCapture::image_to_base64(&image, config.capture.compress_quality)?;
+/// 批量分析的帧数，限制在 1 到 `MAX_ANALYSIS_BATCH` 之间
+fn analysis_batch_size(config: &CaptureConfig) -> usize {
+    config.analysis_batch_size.clamp(1, MAX_ANALYSIS_BATCH)
+}
 
-    // 4. 发送给大模型识别
-    let recent_context = build_recent_summary_context(
-        storage_manager,
-        config.capture.recent_summary_limit,
-        config.capture.recent_detail_limit,
-    );
-    let prompt = format!(
-        r#"你是屏幕截图分析器。请严格只输出一个可解析的 JSON 对象，不要输出任何解释、Markdown 或代码块。
+/// 队列至少能容纳一批帧，否则批量分析永远凑不满
+fn queue_capacity(config: &CaptureConfig) -> usize {
+    config.analysis_queue_size.max(analysis_batch_size(config))
+}
 
-必须包含以下字段：
-{{
+/// 采集运行期间定期推送状态快照，前端不必轮询
+async fn emit_status_periodically(context: Arc<AnalysisContext>) {
+    loop {
+        tokio::time::sleep(STATUS_EVENT_INTERVAL).await;
+        if let Err(err) = emit_event(context.events.as_ref(), CAPTURE_STATUS_EVENT, context.status()) {
+            eprintln!("推送采集状态失败: {}", err);
+        }
+    }
+}
+
+fn build_status(
+    is_running: &ParkingMutex<bool>,
+    session: &SessionState,
+    telemetry: &ParkingMutex<CaptureTelemetry>,
+) -> CaptureStatus {
+    let idle = *session.idle.lock();
+    let resume_at = session.manual_pause.lock().and_then(|pause| pause.resume_at);
+
+    let mut status = telemetry.lock().snapshot();
+    status.is_capturing = *is_running.lock();
+    status.is_paused = idle.is_some();
+    status.pause_reason = idle.map(|reason| reason.as_str().to_string());
+    status.resume_at = resume_at.map(|at| at.format("%Y-%m-%dT%H:%M:%S").to_string());
+    status
+}
+
+/// 与同一显示器的上一帧比较；有变化时更新上一帧指纹
+fn detect_change(
+    detector: &ChangeDetector,
+    frame: &CapturedFrame,
+    prev_fingerprints: &mut HashMap<String, FrameFingerprint>,
+) -> ChangeResult {
+    let current = detector.fingerprint(&frame.image);
+    let result = match prev_fingerprints.get(&frame.display_id) {
+        Some(prev) => detector.compare(prev, &current),
+        None => ChangeResult {
+            changed: true,
+            region: None,
+        },
+    };
+
+    if result.changed {
+        prev_fingerprints.insert(frame.display_id.clone(), current);
+    }
+    result
+}
+
+/// 在时间线上记录暂停、不在监控时段和恢复，区分"没有活动"和"没有采集"
+fn record_idle_change(
+    storage_manager: &StorageManager,
+    now: DateTime<Local>,
+    idle: Option<IdleReason>,
+    resume_at: Option<DateTime<Local>>,
+) -> Result<(), String> {
+    let summary = match idle {
+        Some(IdleReason::Paused) => match resume_at {
+            Some(at) => format!("监控已暂停，预计 {} 自动恢复", at.format("%H:%M")),
+            None => "监控已暂停".to_string(),
+        },
+        Some(IdleReason::OffSchedule) => "不在监控时段内，暂停采集".to_string(),
+        None => "恢复监控".to_string(),
+    };
+
+    let record = SummaryRecord {
+        timestamp: now.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        summary,
+        action: idle.map(|reason| reason.as_str()).unwrap_or("resumed").to_string(),
+        ..Default::default()
+    };
+    storage_manager.save_summary(&record)
+}
+
+/// 保存截图；内容几乎相同的画面只保存一份，多条记录共用同一个文件
+fn save_screenshot(
+    storage_manager: &StorageManager,
+    image: &DynamicImage,
+    now: &DateTime<Local>,
+    display_id: &str,
+    options: EncodeOptions,
+) -> Option<String> {
+    let key = content_key(image);
+    let filename = format!("{}-{}.jpg", now.format("%Y%m%d-%H%M%S-%.3f"), display_id);
+
+    match storage_manager.store_screenshot(&key, &filename, |path| {
+        ScreenCapture::save_to_file(image, path.to_string_lossy().as_ref(), options)
+    }) {
+        Ok(stored) => Some(stored),
+        Err(err) => {
+            eprintln!("保存截图失败: {}", err);
+            None
+        }
+    }
+}
+
+/// 按截屏顺序等待写入的内容
+enum Commit {
+    Frame(Box<FrameCommit>),
+    /// 画面没有变化，这一时刻仍停留在该显示器最近一条记录的画面上
+    Seen { display_id: String, at: String },
+}
+
+/// 一帧的分析结果，等待按截屏顺序写入
+struct FrameCommit {
+    record: SummaryRecord,
+    alert: Option<PendingAlert>,
+    /// 模型耗时；屏幕文字命中隐私规则、没有上传的帧为 None
+    latency: Option<std::time::Duration>,
+}
+
+/// 写入记录后需要推送的提醒
+struct PendingAlert {
+    alert: AssistantAlert,
+    confidence: f32,
+    threshold: f32,
+}
+
+/// 截图分析结果的字段说明、示例和判定规则（单帧和批量分析共用）
+const ANALYSIS_SCHEMA: &str = r#"{
   "summary": "30-50字的操作概述，描述用户正在做什么、使用什么工具、处理什么内容",
   "detail": "对画面的详细描述：包含主要窗口/界面区域、可见文本、按钮、输入输出、错误提示等具体细节",
   "app": "主要应用或窗口名称，无法判断写 Unknown",
@@ -229,10 +846,10 @@ async fn capture_and_analyze_with_diff(
   "issue_summary": "问题摘要（仅在 has_issue 为 true 时填写，否则空字符串）",
   "suggestion": "解决建议（仅在 has_issue 为 true 时填写，否则空字符串）：根据 detail 中的错误信息，指出最可能的原因，并给出具体可操作的解决步骤",
   "confidence": 对整体分析结果准确性的置信度，0.0-1.0 之间的数值
-}}
+}
 
 示例输出：
-{{
+{
   "summary": "在 VS Code 中编辑 screen-assistant 项目的 Rust 后端代码，正在修改 capture 模块的截图分析提示词",
   "detail": "VS Code 编辑器窗口最大化显示。左侧资源管理器展开 src-tauri/src/capture 目录，当前打开文件为 mod.rs。编辑区域显示第 215-260 行的 Rust 代码，包含 format! 宏和 JSON 字符串。光标位于第 238 行。右上角显示 Git 分支为 master。底部状态栏显示 UTF-8 编码、LF 换行符、Rust 语言模式。底部终端面板已折叠。窗口标题为 'mod.rs - screen-assistant - Visual Studio Code'。",
   "app": "Visual Studio Code",
@@ -241,40 +858,283 @@ async fn capture_and_analyze_with_diff(
   "issue_summary": "",
   "suggestion": "",
   "confidence": 0.95
-}}
+}
 
 判定规则：
 - 只有当截图中出现明确错误/失败/阻塞提示时，has_issue 才为 true
 - issue_type 用 2-6 个词概括问题（如 编译错误/网络错误/权限不足/界面卡死）
 - issue_summary 必须具体指出错误内容或提示文本，不要泛泛而谈
 - detail 只描述可见信息，不要猜测未显示的内容
+"#;
+
+/// 分析单个显示器的一帧
+async fn analyze_frame(context: &AnalysisContext, mut pending: PendingFrame) -> Result<FrameCommit, String> {
+    let settings = context.settings();
+    pending.screen_text = recognize_screen_text(
+        &settings.ocr,
+        &settings.scrubber,
+        &pending.frame.image,
+        settings.config.capture.ocr_max_chars,
+    )
+    .await;
+    // 屏幕文字在本地就能判断是否命中隐私规则，命中时画面不上传
+    if settings.privacy.matches_text(&pending.screen_text) {
+        return Ok(private_commit(context, &settings.config, &pending.frame, None));
+    }
+    analyze_recognized_frame(context, &settings, pending).await
+}
+
+/// 已经识别过屏幕文字的一帧单独请求模型分析
+async fn analyze_recognized_frame(
+    context: &AnalysisContext,
+    settings: &LiveSettings,
+    pending: PendingFrame,
+) -> Result<FrameCommit, String> {
+    let config = &settings.config;
+    let frame = &pending.frame;
+    let now = frame.captured_at;
+
+    // 1. 转换为 base64
+    let image_base64 = ScreenCapture::image_to_base64(&frame.image, EncodeOptions::upload(&config.capture))?;
 
+    // 2. 发送给大模型识别
+    let recent_context = build_recent_summary_context(
+        &context.storage_manager,
+        now,
+        config.capture.recent_summary_limit,
+        config.capture.recent_detail_limit,
+    );
+    let change_hint = match &pending.change_region {
+        Some(region) => format!(
+            "\n本帧相对上一帧的变化区域（像素，原图 {}x{}）：x={}, y={}, 宽={}, 高={}，请重点关注该区域的新内容。\n",
+            frame.image.width(),
+            frame.image.height(),
+            region.x,
+            region.y,
+            region.width,
+            region.height
+        ),
+        None => String::new(),
+    };
+    let prompt = format!(
+        r#"你是屏幕截图分析器。请严格只输出一个可解析的 JSON 对象，不要输出任何解释、Markdown 或代码块。
+
+必须包含以下字段：
+{}
 近期记录（仅供参考，可能不完整）：
 {}
-"#,
-        recent_context
+{}{}"#,
+        ANALYSIS_SCHEMA,
+        recent_context,
+        change_hint,
+        screen_text_hint(&pending.screen_text)
     );
 
-    let analysis = match model_manager
-        .analyze_image(&config.model, &image_base64, &prompt)
-        .await
-    {
-        Ok(result) => result,
+    let model_started = Instant::now();
+    let analysis = request_analysis(context, settings, &[image_base64.as_str()], &prompt, now).await?;
+    let latency = model_started.elapsed();
+
+    // 3. 解析分析结果
+    let parsed = parse_analysis(&analysis)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| analysis_from_text(&analysis));
+
+    finish_analysis(context, settings, pending, parsed, &recent_context, Some(latency)).await
+}
+
+/// 一次请求分析多帧，模型返回 JSON 数组，每帧仍生成一条独立的记录
+///
+/// 屏幕文字命中隐私规则的帧不上传，从最早命中的一帧起的其余帧直接丢弃；
+/// 其余帧的结果与之一一对应，模型少返回的帧逐帧单独重新分析
+async fn analyze_batch(
+    context: &AnalysisContext,
+    mut batch: Vec<PendingFrame>,
+) -> Vec<(u64, Result<FrameCommit, String>)> {
+    let settings = context.settings();
+    let config = &settings.config;
+    let screen_texts = recognize_screen_texts(
+        &settings.ocr,
+        &settings.scrubber,
+        batch.iter().map(|pending| &pending.frame.image),
+        config.capture.ocr_max_chars,
+    )
+    .await;
+    for (pending, screen_text) in batch.iter_mut().zip(screen_texts) {
+        pending.screen_text = screen_text;
+    }
+
+    let mut results = Vec::new();
+    let blocked_at = batch
+        .iter()
+        .filter(|pending| settings.privacy.matches_text(&pending.screen_text))
+        .map(|pending| pending.frame.captured_at)
+        .min();
+    if let Some(blocked_at) = blocked_at {
+        let (allowed, blocked): (Vec<_>, Vec<_>) = batch
+            .into_iter()
+            .partition(|pending| pending.frame.captured_at < blocked_at);
+        let mut dropped = 0;
+        for pending in blocked {
+            if settings.privacy.matches_text(&pending.screen_text) {
+                let commit = private_commit(context, config, &pending.frame, None);
+                results.push((pending.seq, Ok(commit)));
+            } else {
+                dropped += 1;
+                context.finish(pending.seq, None);
+            }
+        }
+        context.telemetry.lock().record_skipped(dropped);
+        batch = allowed;
+    }
+    if !batch.is_empty() {
+        results.extend(request_batch(context, &settings, batch).await);
+    }
+    results
+}
+
+/// 把已经识别过屏幕文字的多帧合并为一次请求
+async fn request_batch(
+    context: &AnalysisContext,
+    settings: &LiveSettings,
+    batch: Vec<PendingFrame>,
+) -> Vec<(u64, Result<FrameCommit, String>)> {
+    let config = &settings.config;
+    let count = batch.len();
+    let first_at = batch[0].frame.captured_at;
+
+    let mut images = Vec::with_capacity(count);
+    for pending in &batch {
+        match ScreenCapture::image_to_base64(&pending.frame.image, EncodeOptions::upload(&config.capture)) {
+            Ok(encoded) => images.push(encoded),
+            Err(err) => return batch.into_iter().map(|p| (p.seq, Err(err.clone()))).collect(),
+        }
+    }
+
+    let recent_context = build_recent_summary_context(
+        &context.storage_manager,
+        first_at,
+        config.capture.recent_summary_limit,
+        config.capture.recent_detail_limit,
+    );
+
+    let mut frame_hints = String::new();
+    for (index, pending) in batch.iter().enumerate() {
+        let frame = &pending.frame;
+        frame_hints.push_str(&format!(
+            "- 第 {} 张：{}，显示器 {}",
+            index + 1,
+            frame.captured_at.format("%H:%M:%S"),
+            frame.display_id
+        ));
+        if let Some(region) = &pending.change_region {
+            frame_hints.push_str(&format!(
+                "，相对上一帧的变化区域（像素，原图 {}x{}）：x={}, y={}, 宽={}, 高={}",
+                frame.image.width(),
+                frame.image.height(),
+                region.x,
+                region.y,
+                region.width,
+                region.height
+            ));
+        }
+        frame_hints.push('\n');
+        if !pending.screen_text.is_empty() {
+            frame_hints.push_str(&format!(
+                "  屏幕文字（本地 OCR 识别，可能有误）：\n{}\n",
+                normalize_ocr_text(&pending.screen_text, OCR_PROMPT_CHARS)
+            ));
+        }
+    }
+
+    let prompt = format!(
+        r#"你是屏幕截图分析器。下面按时间顺序给出 {} 张截图：
+{}
+请严格只输出一个可解析的 JSON 数组，包含 {} 个对象，第 N 个对象对应第 N 张截图，不要输出任何解释、Markdown 或代码块。
+
+每个对象必须包含以下字段：
+{}
+近期记录（仅供参考，可能不完整）：
+{}"#,
+        count, frame_hints, count, ANALYSIS_SCHEMA, recent_context
+    );
+
+    let image_refs: Vec<&str> = images.iter().map(|s| s.as_str()).collect();
+    let model_started = Instant::now();
+    let analysis = match request_analysis(context, settings, &image_refs, &prompt, first_at).await {
+        Ok(analysis) => analysis,
+        Err(err) => return batch.into_iter().map(|p| (p.seq, Err(err.clone()))).collect(),
+    };
+    // 按帧平摊耗时，自适应间隔和耗时统计按单帧吞吐计算
+    let latency = model_started.elapsed() / count as u32;
+
+    let mut parsed = parse_analysis(&analysis).into_iter();
+    let mut results = Vec::with_capacity(count);
+    for pending in batch {
+        let seq = pending.seq;
+        let result = match parsed.next() {
+            Some(item) => finish_analysis(context, settings, pending, item, &recent_context, Some(latency)).await,
+            None => analyze_recognized_frame(context, settings, pending).await,
+        };
+        results.push((seq, result));
+    }
+    results
+}
+
+/// 调用模型分析图片，失败时推送一次模型错误提醒
+async fn request_analysis(
+    context: &AnalysisContext,
+    settings: &LiveSettings,
+    images: &[&str],
+    prompt: &str,
+    now: DateTime<Local>,
+) -> Result<String, String> {
+    let config = &settings.config;
+    match context.model_manager.analyze_images(&config.model, images, prompt).await {
+        Ok(result) => Ok(result),
         Err(err) => {
             emit_model_error_once(
-                recent_alerts,
-                app_handle,
+                &context.session.recent_alerts,
+                context.events.as_ref(),
                 &err,
                 "capture",
                 now,
                 config.capture.alert_cooldown_seconds,
             );
-            return Err(err);
+            Err(err)
         }
-    };
+    }
+}
+
+/// 处理一帧的解析结果：隐私规则、脱敏、提醒判断、保存截图，生成待提交的记录
+async fn finish_analysis(
+    context: &AnalysisContext,
+    settings: &LiveSettings,
+    pending: PendingFrame,
+    mut parsed: AnalysisResult,
+    recent_context: &str,
+    latency: Option<std::time::Duration>,
+) -> Result<FrameCommit, String> {
+    let config = &settings.config;
+    let model_manager = &context.model_manager;
+    let storage_manager = &context.storage_manager;
+    let session = &context.session;
+    let frame = &pending.frame;
+    let change_region = pending.change_region;
+    let image = &frame.image;
+    let now = frame.captured_at;
+    let timestamp = now.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    // 模型识别出隐私应用或标题：丢弃画面和截图，只写占位记录，并暂停上传一段时间
+    let privacy_action = settings.privacy.check(&parsed);
+    if privacy_action == PrivacyAction::Block {
+        return Ok(private_commit(context, config, frame, latency));
+    }
+
+    // 脱敏：模型从屏幕上读到的密钥、邮箱、手机号等不写入摘要
+    scrub_analysis(&settings.scrubber, &mut parsed);
+    let issue = parse_issue(&parsed, &pending.screen_text);
 
-    // 5. 解析分析结果
-    let mut parsed = parse_analysis(&analysis);
     let alert_threshold = config.capture.alert_confidence_threshold.clamp(0.0, 1.0);
     let issue_message = if parsed.issue_message.is_empty() {
         parsed.summary.clone()
@@ -284,14 +1144,14 @@ async fn capture_and_analyze_with_diff(
     let mut should_emit = false;
     let mut current_issue_key: Option<String> = None;
 
-    if parsed.has_issue && parsed.confidence >= alert_threshold && !should_suppress_alert(&parsed) {
-        let alert_key = build_alert_key(&parsed, &issue_message);
+    if parsed.has_issue && parsed.confidence >= alert_threshold && privacy_action == PrivacyAction::Allow {
+        let alert_key = build_alert_key(&parsed, issue.as_ref(), &issue_message);
         current_issue_key = Some(alert_key.clone());
 
-        let last_key = last_issue_key.lock().clone();
+        let last_key = session.last_issue_key.lock().clone();
         if last_key.as_deref() != Some(alert_key.as_str()) {
             should_emit = should_emit_alert(
-                recent_alerts,
+                &session.recent_alerts,
                 &alert_key,
                 now,
                 config.capture.alert_cooldown_seconds,
@@ -299,7 +1159,7 @@ async fn capture_and_analyze_with_diff(
         }
 
         if should_emit && parsed.suggestion.trim().is_empty() {
-            match generate_issue_suggestion(&model_manager, &config, &recent_context, &parsed).await {
+            match generate_issue_suggestion(model_manager, config, recent_context, &parsed, issue.as_ref()).await {
                 Ok(suggestion) => parsed.suggestion = suggestion,
                 Err(err) => {
                     eprintln!("生成建议失败: {}", err);
@@ -309,14 +1169,273 @@ async fn capture_and_analyze_with_diff(
         }
     }
 
-    *last_issue_key.lock() = current_issue_key;
+    *session.last_issue_key.lock() = current_issue_key;
 
-    // 6. 保存摘要
-    let timestamp = now.format("%Y-%m-%dT%H:%M:%S").to_string();
-    let issue_summary = issue_message.clone();
+    // 4. 只为分析过的画面保存截图
+    let screenshot_ref = save_screenshot(
+        storage_manager,
+        image,
+        &now,
+        &frame.display_id,
+        EncodeOptions::save(&config.capture),
+    );
 
+    // 5. 生成摘要，由 sequencer 按截屏顺序写入
     let summary = SummaryRecord {
-        timestamp: timestamp.clone(),
+        change_region,
+        screen_text: pending.screen_text,
+        issue,
+        ..record_from_analysis(&parsed, frame, screenshot_ref.unwrap_or_default(), &context.rules())
+    };
+
+    // 6. 如果检测到困难，写入记录后主动推送提示
+    let alert = if parsed.has_issue && should_emit {
+        Some(PendingAlert {
+            alert: AssistantAlert {
+                timestamp,
+                issue_type: parsed.issue_type,
+                message: issue_message,
+                suggestion: parsed.suggestion,
+                issue: summary.issue.clone(),
+            },
+            confidence: parsed.confidence,
+            threshold: alert_threshold,
+        })
+    } else {
+        None
+    };
+
+    Ok(FrameCommit {
+        record: summary,
+        alert,
+        latency,
+    })
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct AssistantAlert {
+    pub timestamp: String,
+    pub issue_type: String,
+    pub message: String,
+    pub suggestion: String,
+    /// 解析出的结构化报错，未识别时为 null
+    pub issue: Option<IssueInfo>,
+}
+
+/// 立即截取当前画面，结合近期记录回答用户的问题，不依赖定时监控是否在运行
+///
+/// 这一帧按普通记录保存（含截图），返回给用户的是模型对问题的回答。监控运行中时记录交给当前会话，
+/// 排在已经入队的帧之后提交并参与合并；隐私冷却期内不截屏上传
+pub async fn ask_about_screen(config: &Config, question: &str, ask: &ScreenAskSession) -> Result<String, String> {
+    if let Some(until) = ask.session.privacy_pause_until(Local::now()) {
+        return Err(format!(
+            "命中隐私规则，{} 之前不会上传画面；可以直接提问，从历史记录中查询",
+            until.format("%H:%M:%S")
+        ));
+    }
+
+    let mut frames = ScreenCapture::capture_frames(&config.capture)?;
+    let started = Instant::now();
+    let result = answer_about_frames(config, question, &mut frames, &ask.session).await;
+    let records = result
+        .as_ref()
+        .map(|(_, records)| records.iter().flatten().cloned().collect())
+        .unwrap_or_else(|_| Vec::new());
+    match &ask.context {
+        Some(context) => {
+            // 回答返回后才取提交序号：定时分析的记录不必等这次提问的模型调用
+            for record in records {
+                let commit = FrameCommit {
+                    record,
+                    alert: None,
+                    latency: Some(started.elapsed()),
+                };
+                context.finish(context.reserve_seq(), Some(commit));
+            }
+        }
+        None => {
+            let storage_manager = StorageManager::new();
+            let merge = SpanPolicy::from_config(&config.storage);
+            for record in &records {
+                if let Err(err) = storage_manager.save_summary_merged(record, merge.as_ref()) {
+                    eprintln!("保存记录失败: {}", err);
+                }
+            }
+        }
+    }
+    result.map(|(answer, _)| answer)
+}
+
+/// 即时提问时的采集状态：隐私冷却期，以及正在运行的会话（记录交给它按顺序提交）
+#[derive(Clone)]
+pub struct ScreenAskSession {
+    session: SessionState,
+    context: Option<Arc<AnalysisContext>>,
+}
+
+/// 上传截图回答问题，返回回答和每一帧对应的记录（模型没有返回分析结果的帧为 None）
+///
+/// 有一帧命中隐私规则时开始隐私冷却，不返回模型的回答
+async fn answer_about_frames(
+    config: &Config,
+    question: &str,
+    frames: &mut [CapturedFrame],
+    session: &SessionState,
+) -> Result<(String, Vec<Option<SummaryRecord>>), String> {
+    let capture_config = &config.capture;
+    let storage_manager = StorageManager::new();
+    let model_manager = ModelManager::new();
+
+    for frame in frames.iter_mut() {
+        apply_redactions(&mut frame.image, &frame.display_id, &capture_config.redaction_regions);
+    }
+    let now = frames.first().map(|frame| frame.captured_at).unwrap_or_else(Local::now);
+
+    let privacy = PrivacyFilter::from_config(capture_config);
+    let scrubber = Scrubber::from_config(capture_config);
+    let ocr = ocr_from_config(capture_config);
+    let screen_texts = recognize_screen_texts(
+        &ocr,
+        &scrubber,
+        frames.iter().map(|frame| &frame.image),
+        capture_config.ocr_max_chars,
+    )
+    .await;
+
+    // 屏幕文字命中隐私规则时不上传任何画面，命中的帧写占位记录
+    let blocked_at = frames
+        .iter()
+        .zip(&screen_texts)
+        .find(|(_, text)| privacy.matches_text(text))
+        .map(|(frame, _)| frame.captured_at);
+    if let Some(at) = blocked_at {
+        session.start_privacy_pause(at, capture_config);
+        let records = frames
+            .iter()
+            .zip(&screen_texts)
+            .map(|(frame, text)| privacy.matches_text(text).then(|| private_placeholder(frame)))
+            .collect();
+        return Ok((PRIVATE_ANSWER.to_string(), records));
+    }
+
+    let mut images = Vec::with_capacity(frames.len());
+    let mut screen_hint = String::new();
+    for (index, (frame, text)) in frames.iter().zip(&screen_texts).enumerate() {
+        images.push(ScreenCapture::image_to_base64(&frame.image, EncodeOptions::upload(capture_config))?);
+        if !text.is_empty() {
+            screen_hint.push_str(&format!(
+                "\n第 {} 张截图的屏幕文字（本地 OCR 识别，可能有误，仅供参考）：\n{}\n",
+                index + 1,
+                normalize_ocr_text(text, OCR_PROMPT_CHARS)
+            ));
+        }
+    }
+
+    let recent_context = build_recent_summary_context(
+        &storage_manager,
+        now,
+        capture_config.recent_summary_limit,
+        capture_config.recent_detail_limit,
+    );
+    let prompt = format!(
+        r#"你是屏幕助手。用户正在看着屏幕向你提问，请结合截图（共 {} 张，每个显示器一张）和近期记录回答。
+
+用户问题：{}
+
+请严格只输出一个可解析的 JSON 对象，不要输出任何解释、Markdown 或代码块：
+{{
+  "answer": "对用户问题的回答，可以使用 Markdown；涉及报错时说明含义、可能原因和具体解决步骤",
+  "frames": [每张截图一个对象，按顺序对应]
+}}
+
+frames 中每个对象必须包含以下字段：
+{}
+近期记录（仅供参考，可能不完整）：
+{}{}"#,
+        images.len(),
+        question,
+        ANALYSIS_SCHEMA,
+        recent_context,
+        screen_hint
+    );
+
+    let image_refs: Vec<&str> = images.iter().map(|s| s.as_str()).collect();
+    let response = model_manager.analyze_images(&config.model, &image_refs, &prompt).await?;
+
+    let answer = extract_json_value(&response)
+        .and_then(|json| json.get("answer").and_then(|v| v.as_str()).map(|s| s.trim().to_string()))
+        .filter(|answer| !answer.is_empty())
+        .unwrap_or_else(|| response.trim().to_string());
+    let answer = scrubber.scrub(&answer);
+
+    // 每一帧生成一条普通记录，之后的提问和历史里都能查到
+    let rules = load_activity_rules(&storage_manager);
+    let mut parsed = parse_analysis(&response).into_iter();
+    let mut records = Vec::with_capacity(frames.len());
+    let mut blocked_at = None;
+    for (frame, screen_text) in frames.iter().zip(screen_texts) {
+        // 模型少返回的帧没有分析结果，不生成记录
+        let mut result = match parsed.next() {
+            Some(result) => result,
+            None => {
+                records.push(None);
+                continue;
+            }
+        };
+        let record = if privacy.check(&result) == PrivacyAction::Block {
+            blocked_at = Some(frame.captured_at);
+            private_placeholder(frame)
+        } else {
+            scrub_analysis(&scrubber, &mut result);
+            let screenshot_ref = save_screenshot(
+                &storage_manager,
+                &frame.image,
+                &frame.captured_at,
+                &frame.display_id,
+                EncodeOptions::save(capture_config),
+            );
+            SummaryRecord {
+                issue: parse_issue(&result, &screen_text),
+                screen_text,
+                ..record_from_analysis(&result, frame, screenshot_ref.unwrap_or_default(), &rules)
+            }
+        };
+        records.push(Some(record));
+    }
+
+    // 命中隐私规则：和定时分析一样暂停上传，回答可能复述了隐私画面的内容，不展示
+    if let Some(at) = blocked_at {
+        session.start_privacy_pause(at, capture_config);
+        return Ok((PRIVATE_ANSWER.to_string(), records));
+    }
+
+    Ok((answer, records))
+}
+
+/// 读取自定义分类规则；文件无法解析时只使用内置规则
+fn load_activity_rules(storage_manager: &StorageManager) -> ActivityRules {
+    ActivityRules::load(&storage_manager.activity_rules_path()).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        ActivityRules::default()
+    })
+}
+
+/// 由解析结果生成一条记录（定时分析和即时提问共用），并按分类规则补充应用名、文件、活动类型和项目
+fn record_from_analysis(
+    parsed: &AnalysisResult,
+    frame: &CapturedFrame,
+    detail_ref: String,
+    rules: &ActivityRules,
+) -> SummaryRecord {
+    let info = rules.classify(&parsed.app, &parsed.summary, &parsed.detail);
+    let issue_summary = if parsed.issue_message.is_empty() {
+        parsed.summary.clone()
+    } else {
+        parsed.issue_message.clone()
+    };
+
+    SummaryRecord {
+        timestamp: frame.captured_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
         summary: parsed.summary.clone(),
         app: parsed.app.clone(),
         action: if parsed.has_issue { "issue".to_string() } else { "active".to_string() },
@@ -327,77 +1446,131 @@ async fn capture_and_analyze_with_diff(
         suggestion: parsed.suggestion.clone(),
         confidence: parsed.confidence,
         detail: parsed.detail.clone(),
-        detail_ref: screenshot_ref.unwrap_or_default(),
-    };
-
-    storage_manager.save_summary(&summary)?;
-
-    // 7. 如果检测到困难，主动推送提示
-    if parsed.has_issue && should_emit {
-        let alert_message = AssistantAlert {
-            timestamp: timestamp.clone(),
-            issue_type: parsed.issue_type,
-            message: issue_message,
-            suggestion: parsed.suggestion,
-        };
-
-        let mut alert_log = String::new();
-        alert_log.push_str(&format!("time: {}\n", timestamp));
-        alert_log.push_str(&format!("issue_type: {}\n", alert_message.issue_type));
-        alert_log.push_str(&format!("message: {}\n", alert_message.message));
-        if !alert_message.suggestion.is_empty() {
-            alert_log.push_str(&format!("suggestion: {}\n", alert_message.suggestion));
-        }
-        alert_log.push_str(&format!(
-            "confidence: {:.2}\nthreshold: {:.2}\n",
-            parsed.confidence, alert_threshold
-        ));
-        if let Err(err) = storage_manager.write_log_snapshot("assistant-alert", &alert_log) {
-            eprintln!("写入提醒日志失败: {}", err);
-        }
+        detail_ref,
+        display_id: frame.display_id.clone(),
+        change_region: None,
+        app_name: info.app,
+        file: info.file.unwrap_or_default(),
+        activity_type: info.activity_type,
+        project: info.project.unwrap_or_default(),
+        start: String::new(),
+        frame_count: 1,
+        screen_text: String::new(),
+        issue: None,
+        last_seen: String::new(),
+    }
+}
 
-        if let Err(err) = app_handle.emit("assistant-alert", alert_message) {
-            eprintln!("发送提醒失败: {}", err);
-        }
+/// 命中隐私规则：开始隐私冷却，只提交占位记录
+fn private_commit(
+    context: &AnalysisContext,
+    config: &Config,
+    frame: &CapturedFrame,
+    latency: Option<std::time::Duration>,
+) -> FrameCommit {
+    context.session.start_privacy_pause(frame.captured_at, &config.capture);
+    FrameCommit {
+        record: private_placeholder(frame),
+        alert: None,
+        latency,
     }
+}
 
-    Ok(true)  // 返回true表示已分析
+/// 命中隐私规则时写入的占位记录，不含画面内容和截图
+fn private_placeholder(frame: &CapturedFrame) -> SummaryRecord {
+    SummaryRecord {
+        timestamp: frame.captured_at.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        summary: "隐私内容，已屏蔽".to_string(),
+        app: "Private".to_string(),
+        action: "private".to_string(),
+        display_id: frame.display_id.clone(),
+        ..Default::default()
+    }
 }
 
-#[derive(Clone, serde::Serialize)]
-pub struct AssistantAlert {
-    pub timestamp: String,
-    pub issue_type: String,
-    pub message: String,
-    pub suggestion: String,
+/// 脱敏解析结果中会写入记录的文本
+fn scrub_analysis(scrubber: &Scrubber, parsed: &mut AnalysisResult) {
+    parsed.summary = scrubber.scrub(&parsed.summary);
+    parsed.detail = scrubber.scrub(&parsed.detail);
+    parsed.issue_message = scrubber.scrub(&parsed.issue_message);
+    parsed.suggestion = scrubber.scrub(&parsed.suggestion);
 }
 
-fn should_suppress_alert(parsed: &AnalysisResult) -> bool {
-    let app = parsed.app.to_lowercase();
-    let combined = format!(
-        "{} {} {} {}",
-        parsed.app,
-        parsed.summary,
-        parsed.detail,
-        parsed.issue_message
-    )
-    .to_lowercase();
+/// 在阻塞线程中识别画面文字，结果经过脱敏和截断；未开启识别或识别失败时返回空字符串
+async fn recognize_screen_text(
+    engine: &Arc<dyn OcrEngine>,
+    scrubber: &Scrubber,
+    image: &DynamicImage,
+    max_chars: usize,
+) -> String {
+    recognize_screen_texts(engine, scrubber, std::iter::once(image), max_chars)
+        .await
+        .pop()
+        .unwrap_or_default()
+}
 
-    let markers = ["历史", "对话", "聊天", "提醒", "警告", "设置"];
-    let has_marker = markers.iter().any(|marker| combined.contains(marker));
+/// 同时识别多帧的文字，按顺序返回；各帧在各自的阻塞线程中并行识别
+async fn recognize_screen_texts<'a>(
+    engine: &Arc<dyn OcrEngine>,
+    scrubber: &Scrubber,
+    images: impl Iterator<Item = &'a DynamicImage>,
+    max_chars: usize,
+) -> Vec<String> {
+    if !engine.enabled() {
+        return images.map(|_| String::new()).collect();
+    }
 
-    if app.contains("screen assistant") {
-        return has_marker;
+    let tasks: Vec<_> = images
+        .map(|image| {
+            let task_engine = engine.clone();
+            let image = image.clone();
+            tokio::task::spawn_blocking(move || task_engine.recognize(&image))
+        })
+        .collect();
+    let mut texts = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        let text = match task.await {
+            Ok(Ok(text)) => scrubber.scrub(&normalize_ocr_text(&text, max_chars)),
+            Ok(Err(err)) => {
+                eprintln!("文字识别失败（{}）: {}", engine.name(), err);
+                String::new()
+            }
+            Err(err) => {
+                eprintln!("文字识别任务异常: {}", err);
+                String::new()
+            }
+        };
+        texts.push(text);
     }
+    texts
+}
 
-    if (app.is_empty() || app == "unknown") && combined.contains("screen assistant") {
-        return has_marker;
+/// 单帧分析提示词中的屏幕文字部分
+fn screen_text_hint(screen_text: &str) -> String {
+    if screen_text.is_empty() {
+        return String::new();
     }
+    format!(
+        "\n屏幕文字（本地 OCR 识别，可能有误，仅供参考；detail 和 issue_summary 中引用报错、网址等原文时以截图为准，可参考此文字核对）：\n{}\n",
+        normalize_ocr_text(screen_text, OCR_PROMPT_CHARS)
+    )
+}
 
-    false
+/// 有问题时从屏幕原文、画面描述和问题摘要中解析结构化报错，优先使用逐字识别的屏幕原文
+fn parse_issue(parsed: &AnalysisResult, screen_text: &str) -> Option<IssueInfo> {
+    if !parsed.has_issue {
+        return None;
+    }
+    [screen_text, parsed.detail.as_str(), parsed.issue_message.as_str()]
+        .into_iter()
+        .find_map(IssueParser::parse)
 }
 
-fn build_alert_key(parsed: &AnalysisResult, issue_message: &str) -> String {
+/// 提醒去重键：解析出错误码或文件时按两者去重，同一处报错换了措辞也不会重复提醒
+fn build_alert_key(parsed: &AnalysisResult, issue: Option<&IssueInfo>, issue_message: &str) -> String {
+    if let Some(key) = issue.and_then(IssueInfo::dedupe_key) {
+        return key;
+    }
     let issue_type = normalize_key(&parsed.issue_type);
     if !issue_type.is_empty() {
         return issue_type;
@@ -456,7 +1629,7 @@ fn should_emit_alert(
 
 fn emit_model_error_once(
     recent_alerts: &Arc<ParkingMutex<HashMap<String, DateTime<Local>>>>,
-    app_handle: &AppHandle,
+    events: &dyn EventSink,
     detail: &str,
     source: &str,
     now: DateTime<Local>,
@@ -465,7 +1638,7 @@ fn emit_model_error_once(
     let alert = build_model_error_alert(detail, source);
     let key = format!("model:{}:{}", &alert.error_type, &alert.message);
     if should_emit_alert(recent_alerts, &key, now, cooldown_seconds) {
-        let _ = app_handle.emit("model-error", alert);
+        let _ = emit_event(events, "model-error", alert);
     }
 }
 
@@ -481,53 +1654,78 @@ struct AnalysisResult {
     confidence: f32,
 }
 
-fn parse_analysis(analysis: &str) -> AnalysisResult {
-    if let Some(json) = extract_json_value(analysis) {
-        let mut has_issue = json
-            .get("has_issue")
-            .and_then(|v| v.as_bool())
-            .or_else(|| json.get("has_error").and_then(|v| v.as_bool()))
-            .unwrap_or(false);
-        let issue_type = json
-            .get("issue_type")
-            .and_then(|v| v.as_str())
-            .or_else(|| json.get("error_type").and_then(|v| v.as_str()))
-            .unwrap_or("")
-            .to_string();
-        let issue_message = json
-            .get("issue_summary")
-            .and_then(|v| v.as_str())
-            .or_else(|| json.get("error_message").and_then(|v| v.as_str()))
-            .unwrap_or("")
-            .to_string();
-        let detail = json
-            .get("detail")
-            .or_else(|| json.get("detail_description"))
-            .or_else(|| json.get("image_detail"))
-            .or_else(|| json.get("image_description"))
-            .or_else(|| json.get("screen_detail"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let suggestion = json.get("suggestion").and_then(|v| v.as_str()).unwrap_or("").to_string();
-        let confidence = parse_confidence(&json, has_issue);
-
-        if !has_issue && (!issue_type.is_empty() || !issue_message.is_empty() || !suggestion.is_empty()) {
-            has_issue = true;
-        }
-
-        return AnalysisResult {
-            summary: json.get("summary").and_then(|v| v.as_str()).unwrap_or("").to_string(),
-            app: json.get("app").and_then(|v| v.as_str()).unwrap_or("Unknown").to_string(),
-            detail,
-            has_issue,
-            issue_type,
-            issue_message,
-            suggestion,
-            confidence,
-        };
+/// 解析模型输出；批量分析时模型返回 JSON 数组，按顺序每个元素对应一帧
+fn parse_analysis(analysis: &str) -> Vec<AnalysisResult> {
+    match extract_json_value(analysis) {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter(|item| item.is_object())
+            .map(analysis_from_json)
+            .collect(),
+        Some(json) => match json
+            .get("frames")
+            .or_else(|| json.get("results"))
+            .and_then(|v| v.as_array())
+        {
+            Some(items) => items
+                .iter()
+                .filter(|item| item.is_object())
+                .map(analysis_from_json)
+                .collect(),
+            None => vec![analysis_from_json(&json)],
+        },
+        None => vec![analysis_from_text(analysis)],
+    }
+}
+
+fn analysis_from_json(json: &serde_json::Value) -> AnalysisResult {
+    let mut has_issue = json
+        .get("has_issue")
+        .and_then(|v| v.as_bool())
+        .or_else(|| json.get("has_error").and_then(|v| v.as_bool()))
+        .unwrap_or(false);
+    let issue_type = json
+        .get("issue_type")
+        .and_then(|v| v.as_str())
+        .or_else(|| json.get("error_type").and_then(|v| v.as_str()))
+        .unwrap_or("")
+        .to_string();
+    let issue_message = json
+        .get("issue_summary")
+        .and_then(|v| v.as_str())
+        .or_else(|| json.get("error_message").and_then(|v| v.as_str()))
+        .unwrap_or("")
+        .to_string();
+    let detail = json
+        .get("detail")
+        .or_else(|| json.get("detail_description"))
+        .or_else(|| json.get("image_detail"))
+        .or_else(|| json.get("image_description"))
+        .or_else(|| json.get("screen_detail"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let suggestion = json.get("suggestion").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let confidence = parse_confidence(json, has_issue);
+
+    if !has_issue && (!issue_type.is_empty() || !issue_message.is_empty() || !suggestion.is_empty()) {
+        has_issue = true;
     }
 
+    AnalysisResult {
+        summary: json.get("summary").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        app: json.get("app").and_then(|v| v.as_str()).unwrap_or("Unknown").to_string(),
+        detail,
+        has_issue,
+        issue_type,
+        issue_message,
+        suggestion,
+        confidence,
+    }
+}
+
+/// 模型没有按要求输出 JSON 时，按纯文本粗略解析
+fn analysis_from_text(analysis: &str) -> AnalysisResult {
     let has_issue = analysis.to_lowercase().contains("error")
         || analysis.contains("错误")
         || analysis.contains("失败")
@@ -561,7 +1759,18 @@ fn extract_json_value(text: &str) -> Option<serde_json::Value> {
         }
     }
 
-    if let Some(inner) = extract_braced_json(text) {
+    // 批量分析的数组输出：'[' 出现在 '{' 之前时优先按数组截取
+    let array_first = match (text.find('['), text.find('{')) {
+        (Some(bracket), Some(brace)) => bracket < brace,
+        (Some(_), None) => true,
+        _ => false,
+    };
+    let candidates = if array_first {
+        [extract_delimited_json(text, '[', ']'), extract_delimited_json(text, '{', '}')]
+    } else {
+        [extract_delimited_json(text, '{', '}'), extract_delimited_json(text, '[', ']')]
+    };
+    for inner in candidates.into_iter().flatten() {
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&inner) {
             return Some(json);
         }
@@ -593,9 +1802,9 @@ fn extract_fence_body(text: &str) -> Option<String> {
     Some(body)
 }
 
-fn extract_braced_json(text: &str) -> Option<String> {
-    let start = text.find('{')?;
-    let end = text.rfind('}')?;
+fn extract_delimited_json(text: &str, open: char, close: char) -> Option<String> {
+    let start = text.find(open)?;
+    let end = text.rfind(close)?;
     if end <= start {
         return None;
     }
@@ -607,6 +1816,7 @@ async fn generate_issue_suggestion(
     config: &Config,
     recent_context: &str,
     parsed: &AnalysisResult,
+    issue: Option<&IssueInfo>,
 ) -> Result<String, String> {
     let issue_summary = if parsed.issue_message.is_empty() {
         parsed.summary.as_str()
@@ -619,13 +1829,24 @@ async fn generate_issue_suggestion(
         parsed.issue_type.as_str()
     };
 
+    let parsed_issue = match issue {
+        Some(issue) => format!(
+            "\n\n解析出的报错（来自屏幕原文，比上面的描述更准确）:\n- 来源: {}\n- 错误码: {}\n- 位置: {}\n- 原文: {}",
+            issue.tool,
+            if issue.code.is_empty() { "无" } else { issue.code.as_str() },
+            if issue.file.is_empty() { "未知".to_string() } else { issue.location() },
+            issue.message
+        ),
+        None => String::new(),
+    };
     let context = format!(
-        "当前截图分析:\n- summary: {}\n- detail: {}\n- issue_type: {}\n- issue_summary: {}\n- confidence: {:.2}\n\n近期记录:\n{}",
+        "当前截图分析:\n- summary: {}\n- detail: {}\n- issue_type: {}\n- issue_summary: {}\n- confidence: {:.2}{}\n\n近期记录:\n{}",
         parsed.summary,
         parsed.detail,
         issue_type,
         issue_summary,
         parsed.confidence,
+        parsed_issue,
         recent_context
     );
 
@@ -636,10 +1857,12 @@ async fn generate_issue_suggestion(
 
 fn extract_app_from_text(text: &str) -> String {
     let apps = [
-        "Visual Studio Code", "VS Code", "Chrome", "Firefox", "Edge",
-        "微信", "QQ", "钉钉", "飞书", "Slack", "Discord",
+        "Visual Studio Code", "VS Code", "IntelliJ IDEA", "PyCharm", "WebStorm", "GoLand", "CLion", "Rider",
+        "RustRover", "Chrome", "Firefox", "Edge",
+        // 企业微信要在微信之前匹配
+        "企业微信", "微信", "QQ", "钉钉", "飞书", "Lark", "Slack", "Discord",
         "Word", "Excel", "PowerPoint", "Notion", "Obsidian",
-        "Terminal", "PowerShell", "CMD",
+        "iTerm", "Konsole", "Alacritty", "kitty", "WezTerm", "Terminal", "PowerShell", "CMD",
     ];
 
     for app in apps {
@@ -651,39 +1874,22 @@ fn extract_app_from_text(text: &str) -> String {
     "Unknown".to_string()
 }
 
+/// 记录的关键词：摘要分词后去掉停用词，按出现次数取前几个
 fn extract_keywords_from_analysis(analysis: &str) -> Vec<String> {
-    let mut keywords = Vec::new();
-
-    let extensions = [".rs", ".ts", ".js", ".py", ".vue", ".tsx", ".jsx", ".md", ".json"];
-    for ext in extensions {
-        if analysis.contains(ext) {
-            keywords.push(ext.to_string());
-        }
-    }
-
-    let actions = [
-        "编辑", "浏览", "搜索", "调试", "运行", "编写", "阅读", "聊天",
-        "错误", "报错", "困难", "无法", "找不到", "未找到", "卡住", "无响应",
-    ];
-    for action in actions {
-        if analysis.contains(action) {
-            keywords.push(action.to_string());
-        }
-    }
-
-    keywords
+    InfoExtractor::extract_keywords(analysis, RECORD_KEYWORD_LIMIT)
 }
 
 fn build_recent_summary_context(
     storage_manager: &StorageManager,
+    now: DateTime<Local>,
     max_items: usize,
     detail_limit: usize,
 ) -> String {
-    let now = Local::now();
     let date = now.format("%Y-%m-%d").to_string();
     let cutoff = (now - Duration::minutes(RECENT_CONTEXT_MINUTES))
         .format("%Y-%m-%dT%H:%M:%S")
         .to_string();
+    let until = now.format("%Y-%m-%dT%H:%M:%S").to_string();
 
     let records = match storage_manager.get_summaries(&date) {
         Ok(data) => data,
@@ -692,7 +1898,7 @@ fn build_recent_summary_context(
 
     let mut recent: Vec<_> = records
         .into_iter()
-        .filter(|r| r.timestamp >= cutoff)
+        .filter(|r| r.timestamp >= cutoff && r.timestamp <= until)
         .collect();
 
     if recent.is_empty() {
@@ -711,7 +1917,7 @@ fn build_recent_summary_context(
         .into_iter()
         .enumerate()
         .map(|(idx, record)| {
-            let time = record.timestamp.get(11..19).unwrap_or(&record.timestamp);
+            let time = record.time_label();
             let app = if record.app.is_empty() || record.app == "Unknown" {
                 String::new()
             } else {
@@ -0,0 +1,134 @@
+use crate::storage::CaptureConfig;
+use image::DynamicImage;
+use std::sync::Arc;
+
+/// 本地文字识别：识别截图上可见的原始文字
+///
+/// 在分析任务的阻塞线程中调用，实现需要可以跨线程共享
+pub trait OcrEngine: Send + Sync {
+    /// 引擎名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 返回识别出的文字，按行分隔；没有文字时返回空字符串
+    fn recognize(&self, image: &DynamicImage) -> Result<String, String>;
+
+    /// 是否真正执行识别；为 false 时跳过识别步骤
+    fn enabled(&self) -> bool {
+        true
+    }
+}
+
+/// 默认实现：不识别文字
+pub struct NoopOcr;
+
+impl OcrEngine for NoopOcr {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn recognize(&self, _image: &DynamicImage) -> Result<String, String> {
+        Ok(String::new())
+    }
+
+    fn enabled(&self) -> bool {
+        false
+    }
+}
+
+/// 按配置创建识别引擎；引擎未编译进当前版本或初始化失败时退回不识别
+pub fn ocr_from_config(config: &CaptureConfig) -> Arc<dyn OcrEngine> {
+    match config.ocr_engine.trim().to_lowercase().as_str() {
+        "" | "none" => Arc::new(NoopOcr),
+        #[cfg(feature = "ocr-tesseract")]
+        "tesseract" => Arc::new(tesseract_engine::TesseractOcr::new(config)),
+        other => {
+            eprintln!("不支持的文字识别引擎 {}（构建时需启用对应的 cargo feature），已关闭文字识别", other);
+            Arc::new(NoopOcr)
+        }
+    }
+}
+
+/// 整理识别结果：去掉空行和行首尾空白，超出长度时截断
+pub fn normalize_ocr_text(text: &str, max_chars: usize) -> String {
+    let mut normalized = String::new();
+    let mut count = 0;
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let line_chars = line.chars().count();
+        if count + line_chars > max_chars {
+            let rest: String = line.chars().take(max_chars.saturating_sub(count)).collect();
+            if !rest.is_empty() {
+                normalized.push_str(&rest);
+                normalized.push('\n');
+            }
+            break;
+        }
+        normalized.push_str(line);
+        normalized.push('\n');
+        count += line_chars + 1;
+    }
+    normalized.trim_end().to_string()
+}
+
+#[cfg(feature = "ocr-tesseract")]
+mod tesseract_engine {
+    use super::OcrEngine;
+    use crate::storage::CaptureConfig;
+    use image::DynamicImage;
+    use std::cell::RefCell;
+    use tesseract::Tesseract;
+
+    thread_local! {
+        /// Tesseract 实例不能跨线程共享，每个阻塞线程各自缓存一份，避免每帧重新加载语言数据
+        static INSTANCE: RefCell<Option<((Option<String>, String), Tesseract)>> = const { RefCell::new(None) };
+    }
+
+    /// 基于 Tesseract 的识别引擎，需要系统中的 tessdata 语言数据
+    pub struct TesseractOcr {
+        data_path: Option<String>,
+        languages: String,
+    }
+
+    impl TesseractOcr {
+        pub fn new(config: &CaptureConfig) -> Self {
+            let data_path = Some(config.ocr_data_path.trim().to_string()).filter(|p| !p.is_empty());
+            let languages = match config.ocr_languages.trim() {
+                "" => "chi_sim+eng".to_string(),
+                languages => languages.to_string(),
+            };
+            Self { data_path, languages }
+        }
+    }
+
+    impl OcrEngine for TesseractOcr {
+        fn name(&self) -> &'static str {
+            "tesseract"
+        }
+
+        fn recognize(&self, image: &DynamicImage) -> Result<String, String> {
+            let key = (self.data_path.clone(), self.languages.clone());
+            let cached = INSTANCE.with(|cell| {
+                cell.borrow_mut()
+                    .take()
+                    .filter(|(cached_key, _)| *cached_key == key)
+                    .map(|(_, tess)| tess)
+            });
+            let tess = match cached {
+                Some(tess) => tess,
+                None => Tesseract::new(self.data_path.as_deref(), Some(&self.languages))
+                    .map_err(|e| format!("初始化 Tesseract 失败: {}", e))?,
+            };
+
+            let gray = image.to_luma8();
+            let (width, height) = gray.dimensions();
+            let mut tess = tess
+                .set_frame(gray.as_raw(), width as i32, height as i32, 1, width as i32)
+                .map_err(|e| format!("文字识别失败: {}", e))?
+                .recognize()
+                .map_err(|e| format!("文字识别失败: {}", e))?;
+            let text = tess.get_text().map_err(|e| format!("文字识别失败: {}", e))?;
+
+            INSTANCE.with(|cell| *cell.borrow_mut() = Some((key, tess)));
+            Ok(text)
+        }
+    }
+}
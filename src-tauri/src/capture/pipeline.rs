@@ -0,0 +1,165 @@
+use super::CapturedFrame;
+use crate::storage::ChangeRegion;
+use parking_lot::Mutex as ParkingMutex;
+use std::collections::{BTreeMap, VecDeque};
+use tokio::sync::Notify;
+
+/// 等待分析的一帧
+pub struct PendingFrame {
+    /// 入队序号，按截屏时间递增，用于按顺序提交记录
+    pub seq: u64,
+    pub frame: CapturedFrame,
+    pub change_region: Option<ChangeRegion>,
+    /// 本地文字识别结果，分析任务开始时填写
+    pub screen_text: String,
+}
+
+/// 截屏与分析之间的有界队列
+///
+/// 队列满时丢弃最旧的帧、保留最新的帧，模型变慢时分析的总是最近的画面
+pub struct FrameQueue {
+    state: ParkingMutex<QueueState>,
+    /// 有新帧或队列关闭时唤醒分析任务
+    available: Notify,
+    /// 有帧被取走时唤醒等待空位的截屏任务
+    space: Notify,
+}
+
+struct QueueState {
+    frames: VecDeque<PendingFrame>,
+    /// 队列容量，运行中可随配置调整
+    capacity: usize,
+    closed: bool,
+}
+
+impl FrameQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: ParkingMutex::new(QueueState {
+                frames: VecDeque::new(),
+                capacity: capacity.max(1),
+                closed: false,
+            }),
+            available: Notify::new(),
+            space: Notify::new(),
+        }
+    }
+
+    /// 放入一帧，返回因队列已满被挤掉的旧帧
+    pub fn push(&self, pending: PendingFrame) -> Vec<PendingFrame> {
+        let mut dropped = Vec::new();
+        {
+            let mut state = self.state.lock();
+            while state.frames.len() >= state.capacity {
+                match state.frames.pop_front() {
+                    Some(old) => dropped.push(old),
+                    None => break,
+                }
+            }
+            state.frames.push_back(pending);
+        }
+        self.available.notify_one();
+        dropped
+    }
+
+    /// 修改队列容量；调小时多出的旧帧在下次放入时被挤掉
+    pub fn set_capacity(&self, capacity: usize) {
+        self.state.lock().capacity = capacity.max(1);
+        self.space.notify_waiters();
+    }
+
+    /// 等待队列有空位（回放时不丢帧，由队列反压控制回放速度）
+    pub async fn wait_for_space(&self) {
+        loop {
+            let notified = self.space.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let state = self.state.lock();
+                if state.frames.len() < state.capacity {
+                    return;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// 取出最早的一帧；队列关闭且为空时返回 None
+    pub async fn pop(&self) -> Option<PendingFrame> {
+        loop {
+            let notified = self.available.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.state.lock();
+                if let Some(pending) = state.frames.pop_front() {
+                    drop(state);
+                    self.space.notify_one();
+                    return Some(pending);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            notified.await;
+        }
+    }
+
+    /// 不等待地取出最多 `max` 帧（批量分析时凑齐已经在排队的帧）
+    pub fn try_pop_many(&self, max: usize) -> Vec<PendingFrame> {
+        let taken: Vec<PendingFrame> = {
+            let mut state = self.state.lock();
+            let count = max.min(state.frames.len());
+            state.frames.drain(..count).collect()
+        };
+        if !taken.is_empty() {
+            self.space.notify_one();
+        }
+        taken
+    }
+
+    /// 关闭队列，分析任务处理完剩余的帧后退出
+    pub fn close(&self) {
+        self.state.lock().closed = true;
+        self.available.notify_waiters();
+    }
+
+    /// 清空队列，返回尚未分析的帧
+    pub fn clear(&self) -> Vec<PendingFrame> {
+        let cleared = self.state.lock().frames.drain(..).collect();
+        self.space.notify_waiters();
+        cleared
+    }
+}
+
+/// 按入队序号提交分析结果
+///
+/// 多个分析任务并发时完成顺序不确定，先完成的结果在这里等待前面的帧，
+/// 保证记录按截屏时间顺序写入
+pub struct CommitSequencer<T> {
+    next: u64,
+    pending: BTreeMap<u64, Option<T>>,
+}
+
+impl<T> CommitSequencer<T> {
+    pub fn new() -> Self {
+        Self {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// 标记一帧已完成（None 表示失败或被丢弃），返回可以按顺序提交的结果
+    pub fn complete(&mut self, seq: u64, result: Option<T>) -> Vec<T> {
+        self.pending.insert(seq, result);
+
+        let mut ready = Vec::new();
+        while let Some(result) = self.pending.remove(&self.next) {
+            ready.extend(result);
+            self.next += 1;
+        }
+        ready
+    }
+}
@@ -0,0 +1,95 @@
+use super::AnalysisResult;
+use crate::storage::CaptureConfig;
+use regex::Regex;
+
+/// 隐私规则的处理结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrivacyAction {
+    /// 正常记录
+    Allow,
+    /// 正常记录，但不推送提醒（助手自身窗口）
+    SuppressAlert,
+    /// 丢弃画面和截图，只写占位记录，并暂停上传一段时间
+    Block,
+}
+
+/// 隐私过滤：按应用名和标题/关键词规则判断分析结果是否需要屏蔽
+pub struct PrivacyFilter {
+    apps: Vec<String>,
+    patterns: Vec<Regex>,
+}
+
+impl PrivacyFilter {
+    pub fn from_config(config: &CaptureConfig) -> Self {
+        let apps = config
+            .privacy_apps
+            .iter()
+            .map(|app| app.trim().to_lowercase())
+            .filter(|app| !app.is_empty())
+            .collect();
+
+        let patterns = config
+            .privacy_patterns
+            .iter()
+            .filter(|p| !p.trim().is_empty())
+            .filter_map(|p| match Regex::new(&format!("(?i){}", p)) {
+                Ok(re) => Some(re),
+                Err(err) => {
+                    eprintln!("隐私规则无效 {}: {}", p, err);
+                    None
+                }
+            })
+            .collect();
+
+        Self { apps, patterns }
+    }
+
+    pub(super) fn check(&self, parsed: &AnalysisResult) -> PrivacyAction {
+        let app = parsed.app.to_lowercase();
+        if self.apps.iter().any(|blocked| app.contains(blocked.as_str())) {
+            return PrivacyAction::Block;
+        }
+
+        let combined = format!("{}\n{}\n{}", parsed.app, parsed.summary, parsed.detail);
+        if self.patterns.iter().any(|re| re.is_match(&combined)) {
+            return PrivacyAction::Block;
+        }
+
+        if is_assistant_window(parsed) {
+            return PrivacyAction::SuppressAlert;
+        }
+
+        PrivacyAction::Allow
+    }
+
+    /// 识别出的屏幕原文是否命中隐私关键词规则
+    pub(super) fn matches_text(&self, text: &str) -> bool {
+        !text.is_empty() && self.patterns.iter().any(|re| re.is_match(text))
+    }
+}
+
+/// 内置规则：助手自身的历史/对话/提醒/设置界面里出现的错误文本不应再触发提醒
+fn is_assistant_window(parsed: &AnalysisResult) -> bool {
+    let app = parsed.app.to_lowercase();
+    let combined = format!(
+        "{} {} {} {}",
+        parsed.app,
+        parsed.summary,
+        parsed.detail,
+        parsed.issue_message
+    )
+    .to_lowercase();
+
+    let markers = ["历史", "对话", "聊天", "提醒", "警告", "设置"];
+    let has_marker = markers.iter().any(|marker| combined.contains(marker));
+
+    if app.contains("screen assistant") {
+        return has_marker;
+    }
+
+    if (app.is_empty() || app == "unknown") && combined.contains("screen assistant") {
+        return has_marker;
+    }
+
+    false
+}
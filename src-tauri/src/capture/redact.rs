@@ -0,0 +1,76 @@
+use crate::storage::RedactionRegion;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage, Rgba};
+
+/// 模糊时先缩小到原区域的 1/BLUR_FACTOR 再放大回去
+const BLUR_FACTOR: u32 = 16;
+
+/// 对画面中配置的遮挡区域做涂黑或模糊处理
+///
+/// 在变化检测、上传和保存截图之前执行，保证遮挡区域不会以任何形式离开本机
+pub fn apply_redactions(image: &mut DynamicImage, display_id: &str, regions: &[RedactionRegion]) {
+    for region in regions {
+        if let Some(target) = region.display_id.as_deref() {
+            if !target.is_empty() && target != display_id {
+                continue;
+            }
+        }
+
+        let (x, y, width, height) = match resolve_region(region, image.width(), image.height()) {
+            Some(rect) => rect,
+            None => continue,
+        };
+
+        match region.style.as_str() {
+            "blur" => blur_region(image, x, y, width, height),
+            _ => fill_region(image, x, y, width, height),
+        }
+    }
+}
+
+/// 把配置的区域换算为像素矩形，并裁剪到画面范围内
+fn resolve_region(region: &RedactionRegion, image_width: u32, image_height: u32) -> Option<(u32, u32, u32, u32)> {
+    let (x, y, width, height) = match region.unit.as_str() {
+        "ratio" => (
+            region.x * image_width as f32,
+            region.y * image_height as f32,
+            region.width * image_width as f32,
+            region.height * image_height as f32,
+        ),
+        _ => (region.x, region.y, region.width, region.height),
+    };
+
+    let x0 = x.max(0.0).floor() as u32;
+    let y0 = y.max(0.0).floor() as u32;
+    let x1 = ((x + width).ceil().max(0.0) as u32).min(image_width);
+    let y1 = ((y + height).ceil().max(0.0) as u32).min(image_height);
+
+    if x0 >= x1 || y0 >= y1 {
+        return None;
+    }
+    Some((x0, y0, x1 - x0, y1 - y0))
+}
+
+fn fill_region(image: &mut DynamicImage, x: u32, y: u32, width: u32, height: u32) {
+    let black = Rgba([0, 0, 0, 255]);
+    for py in y..y + height {
+        for px in x..x + width {
+            image.put_pixel(px, py, black);
+        }
+    }
+}
+
+/// 缩小再放大得到强模糊效果，比高斯模糊快得多，文字无法辨认
+fn blur_region(image: &mut DynamicImage, x: u32, y: u32, width: u32, height: u32) {
+    let small_width = (width / BLUR_FACTOR).max(1);
+    let small_height = (height / BLUR_FACTOR).max(1);
+
+    let blurred = image
+        .crop_imm(x, y, width, height)
+        .resize_exact(small_width, small_height, FilterType::Triangle)
+        .resize_exact(width, height, FilterType::Triangle);
+
+    if image.copy_from(&blurred, x, y).is_err() {
+        fill_region(image, x, y, width, height);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::storage::CaptureConfig;
+use chrono::{DateTime, Datelike, Local, NaiveTime};
+
+/// 每周监控时段；未配置任何时段时全天监控
+#[derive(Default)]
+pub struct CaptureSchedule {
+    windows: Vec<ScheduleWindow>,
+}
+
+struct ScheduleWindow {
+    /// 1 = 周一 ... 7 = 周日，为空表示每天
+    days: Vec<u32>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl CaptureSchedule {
+    pub fn from_config(config: &CaptureConfig) -> Self {
+        let windows = config
+            .schedule
+            .iter()
+            .filter_map(|window| {
+                let start = NaiveTime::parse_from_str(window.start.trim(), "%H:%M");
+                let end = NaiveTime::parse_from_str(window.end.trim(), "%H:%M");
+                match (start, end) {
+                    (Ok(start), Ok(end)) => Some(ScheduleWindow {
+                        days: window.days.iter().copied().filter(|d| (1..=7).contains(d)).collect(),
+                        start,
+                        end,
+                    }),
+                    _ => {
+                        eprintln!("监控时段无效: {} - {}", window.start, window.end);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self { windows }
+    }
+
+    /// 当前时间是否处于监控时段内
+    pub fn is_active(&self, now: &DateTime<Local>) -> bool {
+        if self.windows.is_empty() {
+            return true;
+        }
+
+        let weekday = now.weekday().number_from_monday();
+        let time = now.time();
+        self.windows.iter().any(|window| window.contains(weekday, time))
+    }
+}
+
+impl ScheduleWindow {
+    fn on_day(&self, weekday: u32) -> bool {
+        self.days.is_empty() || self.days.contains(&weekday)
+    }
+
+    fn contains(&self, weekday: u32, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            return self.on_day(weekday) && time >= self.start && time < self.end;
+        }
+
+        // 跨午夜的时段（如 22:00 - 02:00），凌晨部分算作前一天的时段
+        let previous_day = if weekday == 1 { 7 } else { weekday - 1 };
+        (self.on_day(weekday) && time >= self.start) || (self.on_day(previous_day) && time < self.end)
+    }
+}
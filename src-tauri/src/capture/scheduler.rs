@@ -1,53 +1,103 @@
-use std::sync::Arc;
+use crate::storage::CaptureConfig;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::time::interval;
 
+/// 连续无变化时每次放大间隔的倍数
+const BACKOFF_FACTOR: f64 = 1.5;
+/// 模型耗时的平滑系数（越大越跟随最新一次耗时）
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// 自适应截屏间隔
+///
+/// - 画面连续无变化时逐步放大间隔，直到 `max_ms`
+/// - 画面变化或检测到问题后立即收紧到 `min_ms`
+/// - 间隔不小于近期模型平均耗时除以并发数，避免分析队列持续积压
+///
+/// 未启用自适应时固定为 `interval_ms`。
 pub struct CaptureScheduler {
-    interval_ms: u64,
-    stop_tx: Option<mpsc::Sender<()>>,
+    min_ms: u64,
+    max_ms: u64,
+    current_ms: u64,
+    adaptive: bool,
+    avg_latency_ms: Option<f64>,
+    /// 同时进行的模型调用数
+    concurrency: u32,
 }
 
 impl CaptureScheduler {
-    pub fn new(interval_ms: u64) -> Self {
+    pub fn new(interval_ms: u64, min_ms: u64, max_ms: u64, adaptive: bool) -> Self {
+        let min_ms = min_ms.max(100);
+        let max_ms = max_ms.max(min_ms);
         Self {
-            interval_ms,
-            stop_tx: None,
+            min_ms,
+            max_ms,
+            current_ms: interval_ms.clamp(min_ms, max_ms),
+            adaptive,
+            avg_latency_ms: None,
+            concurrency: 1,
         }
     }
 
-    pub fn set_interval(&mut self, interval_ms: u64) {
-        self.interval_ms = interval_ms;
+    pub fn from_config(config: &CaptureConfig) -> Self {
+        let mut scheduler = if config.adaptive_interval {
+            Self::new(
+                config.interval_ms,
+                config.min_interval_ms,
+                config.max_interval_ms,
+                true,
+            )
+        } else {
+            Self::new(config.interval_ms, config.interval_ms, config.interval_ms, false)
+        };
+        scheduler.concurrency = config.analysis_concurrency.max(1) as u32;
+        scheduler
     }
 
-    pub async fn start<F>(&mut self, mut callback: F)
-    where
-        F: FnMut() + Send + 'static,
-    {
-        let (stop_tx, mut stop_rx) = mpsc::channel::<()>(1);
-        self.stop_tx = Some(stop_tx);
-
-        let interval_ms = self.interval_ms;
-
-        tokio::spawn(async move {
-            let mut ticker = interval(Duration::from_millis(interval_ms));
-
-            loop {
-                tokio::select! {
-                    _ = ticker.tick() => {
-                        callback();
-                    }
-                    _ = stop_rx.recv() => {
-                        break;
-                    }
-                }
-            }
-        });
+    /// 配置变化后按新参数重建，保留已观测到的模型耗时
+    pub fn reconfigure(&mut self, config: &CaptureConfig) {
+        let avg_latency_ms = self.avg_latency_ms;
+        *self = Self::from_config(config);
+        self.avg_latency_ms = avg_latency_ms;
+    }
+
+    /// 当前生效的截屏间隔（毫秒）
+    pub fn current_interval_ms(&self) -> u64 {
+        if !self.adaptive {
+            return self.current_ms;
+        }
+
+        let latency_floor = (self.avg_latency_ms.unwrap_or(0.0) / self.concurrency as f64).round() as u64;
+        self.current_ms.max(latency_floor).min(self.max_ms)
+    }
+
+    /// 根据本轮已经花掉的时间，计算距离下一次截屏还需等待多久
+    pub fn delay_after(&self, elapsed: Duration) -> Duration {
+        Duration::from_millis(self.current_interval_ms()).saturating_sub(elapsed)
     }
 
-    pub async fn stop(&mut self) {
-        if let Some(tx) = self.stop_tx.take() {
-            let _ = tx.send(()).await;
+    /// 记录一轮截屏的结果，`changed` 表示至少有一帧被送去分析
+    pub fn record_capture(&mut self, changed: bool) {
+        if !self.adaptive {
+            return;
+        }
+
+        if changed {
+            self.current_ms = self.min_ms;
+        } else {
+            let next = (self.current_ms as f64 * BACKOFF_FACTOR).round() as u64;
+            self.current_ms = next.clamp(self.min_ms, self.max_ms);
+        }
+    }
+
+    /// 记录一次模型分析的耗时；检测到问题时收紧间隔
+    pub fn record_analysis(&mut self, latency: Duration, has_issue: bool) {
+        let ms = latency.as_secs_f64() * 1000.0;
+        self.avg_latency_ms = Some(match self.avg_latency_ms {
+            Some(avg) => avg * (1.0 - LATENCY_SMOOTHING) + ms * LATENCY_SMOOTHING,
+            None => ms,
+        });
+
+        if self.adaptive && has_issue {
+            self.current_ms = self.min_ms;
         }
     }
 }
@@ -1,49 +1,241 @@
-use image::{DynamicImage, ImageFormat};
+use crate::storage::CaptureConfig;
+use chrono::{DateTime, Local};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage};
 use screenshots::Screen;
-use std::io::Cursor;
+use serde::Serialize;
+use std::borrow::Cow;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
+/// 拼接模式下的显示器标识
+pub const STITCHED_DISPLAY_ID: &str = "stitched";
+
+/// 显示器信息（供前端选择显示器）
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplaySummary {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+/// 一次截屏得到的画面
+pub struct CapturedFrame {
+    /// 显示器 id；拼接模式为 "stitched"
+    pub display_id: String,
+    pub image: DynamicImage,
+    pub captured_at: DateTime<Local>,
+}
+
+/// JPEG 编码参数
+#[derive(Debug, Clone, Copy)]
+pub struct EncodeOptions {
+    /// JPEG 质量 (1-100)
+    pub quality: u8,
+    /// 最大宽度，0 表示不限制
+    pub max_width: u32,
+    /// 最大高度，0 表示不限制
+    pub max_height: u32,
+}
+
+impl EncodeOptions {
+    /// 上传给模型时使用的编码参数
+    pub fn upload(config: &CaptureConfig) -> Self {
+        Self {
+            quality: config.compress_quality,
+            max_width: config.upload_max_width,
+            max_height: config.upload_max_height,
+        }
+    }
+
+    /// 保存到磁盘时使用的编码参数，未单独设置质量时沿用上传质量
+    pub fn save(config: &CaptureConfig) -> Self {
+        Self {
+            quality: config.save_quality.unwrap_or(config.compress_quality),
+            max_width: config.save_max_width,
+            max_height: config.save_max_height,
+        }
+    }
+}
+
 pub struct ScreenCapture;
 
 impl ScreenCapture {
-    /// 截取主屏幕
-    pub fn capture_primary() -> Result<DynamicImage, String> {
+    /// 列出所有显示器
+    pub fn list_displays() -> Result<Vec<DisplaySummary>, String> {
         let screens = Screen::all().map_err(|e| format!("获取屏幕失败: {}", e))?;
+        Ok(screens
+            .iter()
+            .map(|screen| {
+                let info = screen.display_info;
+                DisplaySummary {
+                    id: info.id,
+                    x: info.x,
+                    y: info.y,
+                    width: info.width,
+                    height: info.height,
+                    scale_factor: info.scale_factor,
+                    is_primary: info.is_primary,
+                }
+            })
+            .collect())
+    }
 
-        let primary = screens
-            .into_iter()
-            .next()
-            .ok_or_else(|| "没有找到屏幕".to_string())?;
+    /// 按配置的截屏模式截取画面
+    ///
+    /// - `primary`：主屏幕
+    /// - `display`：指定 `display_id` 的屏幕，找不到时退回主屏幕
+    /// - `each`：所有屏幕，逐个分析
+    /// - `stitched`：所有屏幕横向拼接为一张图
+    pub fn capture_frames(config: &CaptureConfig) -> Result<Vec<CapturedFrame>, String> {
+        let screens = Screen::all().map_err(|e| format!("获取屏幕失败: {}", e))?;
+        if screens.is_empty() {
+            return Err("没有找到屏幕".to_string());
+        }
 
-        let image = primary
-            .capture()
-            .map_err(|e| format!("截屏失败: {}", e))?;
+        let captured_at = Local::now();
+        match config.capture_mode.as_str() {
+            "each" => Ok(capture_available(screens.iter())?
+                .into_iter()
+                .map(|(screen, image)| CapturedFrame {
+                    display_id: screen.display_info.id.to_string(),
+                    image,
+                    captured_at,
+                })
+                .collect()),
+            "stitched" => Ok(vec![CapturedFrame {
+                display_id: STITCHED_DISPLAY_ID.to_string(),
+                image: capture_stitched(&screens)?,
+                captured_at,
+            }]),
+            "display" => {
+                let screen = config
+                    .display_id
+                    .and_then(|id| screens.iter().find(|s| s.display_info.id == id))
+                    .unwrap_or_else(|| primary_screen(&screens));
+                Ok(vec![CapturedFrame {
+                    display_id: screen.display_info.id.to_string(),
+                    image: capture_screen(screen)?,
+                    captured_at,
+                }])
+            }
+            _ => {
+                let screen = primary_screen(&screens);
+                Ok(vec![CapturedFrame {
+                    display_id: screen.display_info.id.to_string(),
+                    image: capture_screen(screen)?,
+                    captured_at,
+                }])
+            }
+        }
+    }
 
-        let width = image.width();
-        let height = image.height();
-        let rgba = image.into_raw();
+    /// 将图片转换为 Base64
+    pub fn image_to_base64(image: &DynamicImage, options: EncodeOptions) -> Result<String, String> {
+        let bytes = encode_jpeg(image, options)?;
+        Ok(BASE64.encode(bytes))
+    }
 
-        image::RgbaImage::from_raw(width, height, rgba)
-            .map(DynamicImage::ImageRgba8)
-            .ok_or_else(|| "图像转换失败".to_string())
+    /// 保存截图到文件
+    pub fn save_to_file(image: &DynamicImage, path: &str, options: EncodeOptions) -> Result<(), String> {
+        let bytes = encode_jpeg(image, options)?;
+        std::fs::write(path, bytes)
+            .map_err(|e| format!("保存截图失败: {}", e))
     }
+}
 
-    /// 将图片转换为 Base64
-    pub fn image_to_base64(image: &DynamicImage, _quality: u8) -> Result<String, String> {
-        let mut buffer = Cursor::new(Vec::new());
+/// 按最大尺寸等比缩小（不放大），再以指定质量编码为 JPEG
+fn encode_jpeg(image: &DynamicImage, options: EncodeOptions) -> Result<Vec<u8>, String> {
+    let scaled = downscale(image, options.max_width, options.max_height);
+    let rgb = scaled.to_rgb8();
 
-        // 压缩为 JPEG
-        let jpeg = image.to_rgb8();
-        jpeg.write_to(&mut buffer, ImageFormat::Jpeg)
-            .map_err(|e| format!("图片编码失败: {}", e))?;
+    let mut buffer = Vec::new();
+    JpegEncoder::new_with_quality(&mut buffer, options.quality.clamp(1, 100))
+        .encode_image(&rgb)
+        .map_err(|e| format!("图片编码失败: {}", e))?;
 
-        Ok(BASE64.encode(buffer.into_inner()))
+    Ok(buffer)
+}
+
+fn downscale(image: &DynamicImage, max_width: u32, max_height: u32) -> Cow<'_, DynamicImage> {
+    let max_width = if max_width == 0 { u32::MAX } else { max_width };
+    let max_height = if max_height == 0 { u32::MAX } else { max_height };
+
+    if image.width() <= max_width && image.height() <= max_height {
+        return Cow::Borrowed(image);
     }
 
-    /// 保存截图到文件
-    pub fn save_to_file(image: &DynamicImage, path: &str, _quality: u8) -> Result<(), String> {
-        image
-            .save(path)
-            .map_err(|e| format!("保存截图失败: {}", e))
+    // resize 会保持宽高比，结果落在 max_width x max_height 之内
+    Cow::Owned(image.resize(max_width, max_height, FilterType::Triangle))
+}
+
+fn primary_screen(screens: &[Screen]) -> &Screen {
+    screens
+        .iter()
+        .find(|s| s.display_info.is_primary)
+        .unwrap_or(&screens[0])
+}
+
+fn capture_screen(screen: &Screen) -> Result<DynamicImage, String> {
+    let image = screen
+        .capture()
+        .map_err(|e| format!("截屏失败: {}", e))?;
+
+    let width = image.width();
+    let height = image.height();
+    let rgba = image.into_raw();
+
+    image::RgbaImage::from_raw(width, height, rgba)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| "图像转换失败".to_string())
+}
+
+/// 逐个截取屏幕；单个屏幕失败（断开、没有权限等）时跳过并输出原因，全部失败才返回错误
+fn capture_available<'a>(
+    screens: impl Iterator<Item = &'a Screen>,
+) -> Result<Vec<(&'a Screen, DynamicImage)>, String> {
+    let mut captured = Vec::new();
+    let mut errors = Vec::new();
+    for screen in screens {
+        match capture_screen(screen) {
+            Ok(image) => captured.push((screen, image)),
+            Err(err) => {
+                eprintln!("显示器 {} {}", screen.display_info.id, err);
+                errors.push(err);
+            }
+        }
+    }
+    if captured.is_empty() {
+        return Err(errors.pop().unwrap_or_else(|| "没有找到屏幕".to_string()));
+    }
+    Ok(captured)
+}
+
+/// 按屏幕从左到右的顺序横向拼接（各屏缩放比例可能不同，不按虚拟桌面坐标摆放）
+fn capture_stitched(screens: &[Screen]) -> Result<DynamicImage, String> {
+    let mut ordered: Vec<&Screen> = screens.iter().collect();
+    ordered.sort_by_key(|s| (s.display_info.x, s.display_info.y));
+
+    let images: Vec<DynamicImage> = capture_available(ordered.into_iter())?
+        .into_iter()
+        .map(|(_, image)| image)
+        .collect();
+
+    let width: u32 = images.iter().map(|img| img.width()).sum();
+    let height = images.iter().map(|img| img.height()).max().unwrap_or(0);
+    let mut canvas = DynamicImage::new_rgba8(width, height);
+
+    let mut offset_x = 0;
+    for img in &images {
+        canvas
+            .copy_from(img, offset_x, 0)
+            .map_err(|e| format!("拼接截图失败: {}", e))?;
+        offset_x += img.width();
     }
+
+    Ok(canvas)
 }
@@ -0,0 +1,33 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// 采集流程推送事件（提醒、模型错误、采集状态）的出口
+///
+/// 应用内为前端窗口；无界面回放时输出到终端
+pub trait EventSink: Send + Sync {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String>;
+}
+
+impl EventSink for AppHandle {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+        self.emit(event, payload).map_err(|e| e.to_string())
+    }
+}
+
+/// 无界面运行时把事件逐行打印为 JSON；采集状态只在结束时打印一次，不逐条输出
+pub struct ConsoleSink;
+
+impl EventSink for ConsoleSink {
+    fn emit_value(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+        if event != super::CAPTURE_STATUS_EVENT {
+            println!("[{}] {}", event, payload);
+        }
+        Ok(())
+    }
+}
+
+/// 序列化后推送事件
+pub fn emit_event<T: Serialize>(sink: &dyn EventSink, event: &str, payload: T) -> Result<(), String> {
+    let value = serde_json::to_value(payload).map_err(|e| format!("序列化事件失败: {}", e))?;
+    sink.emit_value(event, value)
+}
@@ -0,0 +1,160 @@
+use super::{CapturedFrame, ScreenCapture};
+use crate::storage::CaptureConfig;
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// 画面来源：实时截屏或回放录制的截图
+#[async_trait]
+pub trait FrameSource: Send {
+    /// 获取下一批画面，返回 `Ok(None)` 表示来源已耗尽
+    async fn next_frames(&mut self, config: &CaptureConfig) -> Result<Option<Vec<CapturedFrame>>, String>;
+
+    /// 来源是否自带节奏；为 true 时采集循环不再按截屏间隔等待
+    fn self_paced(&self) -> bool {
+        false
+    }
+}
+
+/// 实时截屏
+pub struct ScreenSource;
+
+#[async_trait]
+impl FrameSource for ScreenSource {
+    async fn next_frames(&mut self, config: &CaptureConfig) -> Result<Option<Vec<CapturedFrame>>, String> {
+        ScreenCapture::capture_frames(config).map(Some)
+    }
+}
+
+/// 回放目录中带时间戳的截图（如 `screenshots/`），按录制时的间隔除以 `speed` 输出
+pub struct ReplaySource {
+    entries: VecDeque<ReplayEntry>,
+    speed: f64,
+    /// 上一帧的录制时间和实际输出时间
+    last: Option<(DateTime<Local>, Instant)>,
+}
+
+struct ReplayEntry {
+    path: PathBuf,
+    captured_at: DateTime<Local>,
+    display_id: String,
+}
+
+impl ReplaySource {
+    /// `speed` 为回放倍速，1.0 为原速，0 表示不等待、尽快回放
+    pub fn new(dir: &Path, speed: f64) -> Result<Self, String> {
+        let dir_entries = fs::read_dir(dir)
+            .map_err(|e| format!("读取回放目录失败 {:?}: {}", dir, e))?;
+
+        let mut entries = Vec::new();
+        for entry in dir_entries {
+            let entry = entry.map_err(|e| format!("读取回放目录失败: {}", e))?;
+            let path = entry.path();
+            let ext = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_lowercase())
+                .unwrap_or_default();
+            if !matches!(ext.as_str(), "jpg" | "jpeg" | "png") {
+                continue;
+            }
+
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            let (captured_at, display_id) = match parse_screenshot_name(stem) {
+                Some(parsed) => parsed,
+                None => match file_modified_time(&path) {
+                    Some(time) => (time, "replay".to_string()),
+                    None => continue,
+                },
+            };
+
+            entries.push(ReplayEntry {
+                path,
+                captured_at,
+                display_id,
+            });
+        }
+
+        if entries.is_empty() {
+            return Err(format!("回放目录中没有截图: {:?}", dir));
+        }
+
+        entries.sort_by_key(|e| e.captured_at);
+
+        Ok(Self {
+            entries: entries.into(),
+            speed: if speed.is_finite() { speed.max(0.0) } else { 1.0 },
+            last: None,
+        })
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[async_trait]
+impl FrameSource for ReplaySource {
+    async fn next_frames(&mut self, _config: &CaptureConfig) -> Result<Option<Vec<CapturedFrame>>, String> {
+        let entry = match self.entries.pop_front() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        // 按录制间隔等待（扣除上一帧分析已经花掉的时间）
+        if let Some((prev_captured, prev_emitted)) = self.last {
+            if self.speed > 0.0 {
+                let recorded = entry
+                    .captured_at
+                    .signed_duration_since(prev_captured)
+                    .to_std()
+                    .unwrap_or_default();
+                let target = recorded.div_f64(self.speed);
+                let elapsed = prev_emitted.elapsed();
+                if target > elapsed {
+                    tokio::time::sleep(target - elapsed).await;
+                }
+            }
+        }
+        self.last = Some((entry.captured_at, Instant::now()));
+
+        let image = image::open(&entry.path)
+            .map_err(|e| format!("读取回放截图失败 {:?}: {}", entry.path, e))?;
+
+        Ok(Some(vec![CapturedFrame {
+            display_id: entry.display_id,
+            image,
+            captured_at: entry.captured_at,
+        }]))
+    }
+
+    fn self_paced(&self) -> bool {
+        true
+    }
+}
+
+/// 解析截图文件名，格式为 `YYYYmmdd-HHMMSS-.mmm[-显示器id]`
+fn parse_screenshot_name(stem: &str) -> Option<(DateTime<Local>, String)> {
+    let base = stem.get(..15)?;
+    let naive = NaiveDateTime::parse_from_str(base, "%Y%m%d-%H%M%S").ok()?;
+    let mut captured_at = Local.from_local_datetime(&naive).single()?;
+
+    let mut rest = &stem[15..];
+    if let Some(millis) = rest.strip_prefix("-.").and_then(|r| r.get(..3)) {
+        if let Ok(ms) = millis.parse::<i64>() {
+            captured_at += chrono::Duration::milliseconds(ms);
+            rest = &rest[5..];
+        }
+    }
+
+    let display_id = rest.strip_prefix('-').filter(|id| !id.is_empty()).unwrap_or("replay");
+    Some((captured_at, display_id.to_string()))
+}
+
+fn file_modified_time(path: &Path) -> Option<DateTime<Local>> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::<Local>::from(modified))
+}
@@ -0,0 +1,123 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// 计算平均耗时和 p95 时使用的最近样本数
+const LATENCY_WINDOW: usize = 200;
+
+/// 采集状态快照，`get_capture_status` 返回，同时定期通过 `capture-status` 事件推送
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureStatus {
+    pub is_capturing: bool,
+    /// 已分析并写入记录的帧数
+    pub record_count: u64,
+    pub last_capture_time: Option<String>,
+    /// 是否暂停中（手动暂停或不在监控时段）
+    pub is_paused: bool,
+    /// 暂停原因："paused" 手动暂停，"off_schedule" 不在监控时段
+    pub pause_reason: Option<String>,
+    /// 手动暂停的自动恢复时间
+    pub resume_at: Option<String>,
+    pub last_analysis_time: Option<String>,
+    /// 因无变化、隐私冷却或队列已满而跳过的帧数
+    pub skipped_count: u64,
+    /// 分析失败的帧数
+    pub failed_count: u64,
+    /// 最近模型调用的平均耗时（毫秒）
+    pub avg_latency_ms: Option<u64>,
+    /// 最近模型调用耗时的 p95（毫秒）
+    pub p95_latency_ms: Option<u64>,
+    /// 当前生效的截屏间隔（毫秒）
+    pub current_interval_ms: u64,
+    pub last_error: Option<String>,
+    pub last_error_time: Option<String>,
+}
+
+/// 采集运行指标，跨多次启动/停止累计
+#[derive(Debug, Default)]
+pub struct CaptureTelemetry {
+    last_capture_time: Option<DateTime<Local>>,
+    last_analysis_time: Option<DateTime<Local>>,
+    analyzed: u64,
+    skipped: u64,
+    failed: u64,
+    latencies_ms: VecDeque<u64>,
+    current_interval_ms: u64,
+    last_error: Option<(DateTime<Local>, String)>,
+}
+
+impl CaptureTelemetry {
+    /// 记录一次截屏
+    pub fn record_capture(&mut self, interval_ms: u64) {
+        self.last_capture_time = Some(Local::now());
+        self.current_interval_ms = interval_ms;
+    }
+
+    pub fn record_skipped(&mut self, count: u64) {
+        self.skipped += count;
+    }
+
+    /// 记录一次模型调用的耗时
+    pub fn record_latency(&mut self, latency: Duration) {
+        if self.latencies_ms.len() >= LATENCY_WINDOW {
+            self.latencies_ms.pop_front();
+        }
+        self.latencies_ms.push_back(latency.as_millis() as u64);
+    }
+
+    /// 记录一条分析结果已写入
+    pub fn record_analyzed(&mut self) {
+        self.analyzed += 1;
+        self.last_analysis_time = Some(Local::now());
+    }
+
+    /// 记录一帧分析失败
+    pub fn record_failure(&mut self, error: &str) {
+        self.failed += 1;
+        self.record_error(error);
+    }
+
+    /// 记录错误但不计入失败帧（如截屏失败）
+    pub fn record_error(&mut self, error: &str) {
+        self.last_error = Some((Local::now(), error.to_string()));
+    }
+
+    /// 平均耗时和 p95（毫秒）
+    fn latency_stats(&self) -> (Option<u64>, Option<u64>) {
+        if self.latencies_ms.is_empty() {
+            return (None, None);
+        }
+
+        let mut sorted: Vec<u64> = self.latencies_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        let avg = sorted.iter().sum::<u64>() / sorted.len() as u64;
+        let p95_index = ((sorted.len() as f64 * 0.95).ceil() as usize).clamp(1, sorted.len()) - 1;
+        (Some(avg), Some(sorted[p95_index]))
+    }
+
+    /// 生成状态快照，运行状态和暂停信息由调用方填写
+    pub fn snapshot(&self) -> CaptureStatus {
+        let (avg_latency_ms, p95_latency_ms) = self.latency_stats();
+        CaptureStatus {
+            is_capturing: false,
+            record_count: self.analyzed,
+            last_capture_time: self.last_capture_time.map(format_time),
+            is_paused: false,
+            pause_reason: None,
+            resume_at: None,
+            last_analysis_time: self.last_analysis_time.map(format_time),
+            skipped_count: self.skipped,
+            failed_count: self.failed,
+            avg_latency_ms,
+            p95_latency_ms,
+            current_interval_ms: self.current_interval_ms,
+            last_error: self.last_error.as_ref().map(|(_, error)| error.clone()),
+            last_error_time: self.last_error.as_ref().map(|(time, _)| format_time(*time)),
+        }
+    }
+}
+
+fn format_time(time: DateTime<Local>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
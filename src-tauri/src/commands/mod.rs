@@ -1,9 +1,17 @@
-use crate::capture::CaptureManager;
+use crate::capture::{ask_about_screen, CaptureManager, CaptureStatus, DisplaySummary, ReplaySource, ScreenCapture};
+use crate::analysis::{
+    self, ActivityRule, InfoExtractor, IssueInfo, RuleTestResult, SessionPolicy, Sessionizer, StatsGroup, StatsRange,
+    TimeStats, Tokenizer, WorkSession,
+};
+use crate::export::{self, TimelapseOptions, TimelapseResult};
 use crate::model::ModelManager;
-use crate::storage::{Config, StorageManager, SummaryRecord, SearchQuery, TimeRange};
-use chrono::{Duration, Local, NaiveDateTime, TimeZone};
+use crate::storage::{parse_timestamp, screenshot_mime, Config, ScreenTextMatch, StorageManager, SummaryRecord, SearchQuery, TimeRange};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
 use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::ipc::Response;
 use tauri::{AppHandle, State};
 use tauri_plugin_shell::ShellExt;
 use tokio::sync::Mutex as TokioMutex;
@@ -14,6 +22,10 @@ pub struct AppState {
 }
 
 const MIN_RECENT_DETAIL_RECORDS: usize = 20;
+/// 一次请求最多生成标题的工作段数
+const SESSION_TITLE_BATCH: usize = 20;
+/// 工作段标题的最大字符数
+const MAX_SESSION_TITLE_CHARS: usize = 30;
 
 impl AppState {
     pub fn new() -> Self {
@@ -31,9 +43,14 @@ pub async fn get_config() -> Result<Config, String> {
 }
 
 #[tauri::command]
-pub async fn save_config(config: Config) -> Result<(), String> {
+pub async fn save_config(state: State<'_, AppState>, config: Config) -> Result<(), String> {
     let storage = StorageManager::new();
-    storage.save_config(&config).map_err(|e| e.to_string())
+    storage.save_config(&config).map_err(|e| e.to_string())?;
+
+    // 监控运行中时立即生效，不需要重启
+    let manager = state.capture_manager.lock().await;
+    manager.update_config(config);
+    Ok(())
 }
 
 #[tauri::command]
@@ -75,10 +92,36 @@ pub async fn start_capture(
     let config = storage.load_config().map_err(|e| e.to_string())?;
 
     let mut manager = state.capture_manager.lock().await;
-    manager.start(config, app_handle).await;
+    manager.start(config, Arc::new(app_handle)).await;
     Ok(())
 }
 
+/// 回放目录中的截图（默认为截图目录），按 speed 倍速走完整的分析流程，返回待回放的帧数
+#[tauri::command]
+pub async fn start_replay(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+    dir: Option<String>,
+    speed: Option<f64>,
+) -> Result<usize, String> {
+    let storage = StorageManager::new();
+    let config = storage.load_config().map_err(|e| e.to_string())?;
+    let dir = match dir {
+        Some(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => storage.screenshots_dir()?,
+    };
+
+    let source = ReplaySource::new(&dir, speed.unwrap_or(1.0))?;
+    let total = source.remaining();
+
+    let mut manager = state.capture_manager.lock().await;
+    if manager.is_running() {
+        return Err("监控正在运行，请先停止监控".to_string());
+    }
+    manager.start_with_source(config, Arc::new(app_handle), Box::new(source)).await;
+    Ok(total)
+}
+
 #[tauri::command]
 pub async fn stop_capture(state: State<'_, AppState>) -> Result<(), String> {
     let mut manager = state.capture_manager.lock().await;
@@ -86,21 +129,28 @@ pub async fn stop_capture(state: State<'_, AppState>) -> Result<(), String> {
     Ok(())
 }
 
+/// 暂停监控，保留会话计数；minutes 大于 0 时到期自动恢复
+#[tauri::command]
+pub async fn pause_capture(state: State<'_, AppState>, minutes: Option<u64>) -> Result<(), String> {
+    let manager = state.capture_manager.lock().await;
+    manager.pause(minutes)
+}
+
+#[tauri::command]
+pub async fn resume_capture(state: State<'_, AppState>) -> Result<(), String> {
+    let manager = state.capture_manager.lock().await;
+    manager.resume()
+}
+
 #[tauri::command]
 pub async fn get_capture_status(state: State<'_, AppState>) -> Result<CaptureStatus, String> {
     let manager = state.capture_manager.lock().await;
-    Ok(CaptureStatus {
-        is_capturing: manager.is_running(),
-        record_count: manager.get_count(),
-        last_capture_time: None,
-    })
+    Ok(manager.status())
 }
 
-#[derive(serde::Serialize)]
-pub struct CaptureStatus {
-    pub is_capturing: bool,
-    pub record_count: u64,
-    pub last_capture_time: Option<String>,
+#[tauri::command]
+pub async fn list_displays() -> Result<Vec<DisplaySummary>, String> {
+    ScreenCapture::list_displays()
 }
 
 #[derive(serde::Deserialize)]
@@ -124,9 +174,12 @@ pub async fn chat_with_assistant(
     // 智能检索相关记录
     let mut search_result = storage.smart_search(&query)?;
 
-    if search_result.records.is_empty() && !query.keywords.is_empty() {
+    if search_result.records.is_empty() && (!query.keywords.is_empty() || query.has_filters()) {
         let mut relaxed = query.clone();
         relaxed.keywords.clear();
+        relaxed.apps.clear();
+        relaxed.activity_types.clear();
+        relaxed.files.clear();
         if let Ok(relaxed_result) = storage.smart_search(&relaxed) {
             if !relaxed_result.records.is_empty() || !relaxed_result.aggregated.is_empty() {
                 search_result = relaxed_result;
@@ -150,6 +203,22 @@ pub async fn chat_with_assistant(
         }
     }
 
+    // 问今天或最近几天时附上工作段；只为今天的工作段请求生成标题，避免回答前等待太久
+    let session_days = match query.time_range {
+        TimeRange::Recent(_) => 0,
+        TimeRange::Today => 1,
+        TimeRange::Days(days) => days,
+    };
+    for offset in (0..session_days).rev() {
+        let date = (Local::now() - Duration::days(offset as i64)).format("%Y-%m-%d").to_string();
+        match load_sessions(&storage, &config, &date, offset == 0).await {
+            Ok(sessions) => search_result
+                .sessions
+                .extend(sessions.into_iter().filter(|s| query.matches_session(s))),
+            Err(err) => eprintln!("读取工作段失败 {}: {}", date, err),
+        }
+    }
+
     // 构建上下文（使用配置中的最大字符数）
     let context = search_result.build_context(config.storage.max_context_chars, query.include_detail);
 
@@ -159,6 +228,19 @@ pub async fn chat_with_assistant(
         .await
 }
 
+/// 立即截取当前画面并回答问题（监控停止时也可用），这一帧同时保存为普通记录
+#[tauri::command]
+pub async fn ask_screen(state: State<'_, AppState>, message: String) -> Result<String, String> {
+    if message.trim().is_empty() {
+        return Err("问题不能为空".to_string());
+    }
+
+    let storage = StorageManager::new();
+    let config = storage.load_config().map_err(|e| e.to_string())?;
+    let ask = state.capture_manager.lock().await.ask_session();
+    ask_about_screen(&config, message.trim(), &ask).await
+}
+
 /// 解析用户问题，提取时间范围和关键词
 fn parse_user_query(message: &str) -> SearchQuery {
     let msg_lower = message.to_lowercase();
@@ -189,9 +271,30 @@ fn parse_user_query(message: &str) -> SearchQuery {
         time_range,
         keywords,
         include_detail,
+        apps: InfoExtractor::mentioned_apps(message),
+        activity_types: extract_activity_types(&msg_lower),
+        files: InfoExtractor::extract_file_from_title(message).into_iter().collect(),
     }
 }
 
+/// 问题中明确提到的活动类型（与 `InfoExtractor::infer_activity_type` 的取值对应）
+fn extract_activity_types(msg_lower: &str) -> Vec<String> {
+    let mapping = [
+        (&["写代码", "编程", "coding"][..], "coding"),
+        (&["浏览网页", "上网", "browsing"][..], "browsing"),
+        (&["查资料", "researching"][..], "researching"),
+        (&["终端", "命令行", "terminal"][..], "terminal"),
+        (&["聊天", "沟通", "communicating"][..], "communicating"),
+        (&["写文档", "documenting"][..], "documenting"),
+    ];
+
+    mapping
+        .iter()
+        .filter(|(triggers, _)| triggers.iter().any(|t| msg_lower.contains(t)))
+        .map(|(_, activity)| activity.to_string())
+        .collect()
+}
+
 fn extract_number(text: &str) -> Option<u32> {
     // 中文数字映射
     let cn_nums = [
@@ -243,6 +346,20 @@ fn extract_keywords(message: &str) -> Vec<String> {
         }
     }
 
+    // 分词得到的其余关键词；时间、提问用语已由时间范围和过滤条件处理，不参与匹配
+    let query_stop_words = [
+        "应用", "操作", "记录", "遇到", "做了", "干了", "看了", "用了", "发生", "事情", "东西",
+        "时间", "工作", "帮我", "告诉", "总结", "一下", "有没有",
+    ];
+    for word in Tokenizer::keywords(message, 5) {
+        if query_stop_words.contains(&word.as_str()) {
+            continue;
+        }
+        if !keywords.iter().any(|kw| kw.to_lowercase() == word) {
+            keywords.push(word);
+        }
+    }
+
     keywords
 }
 
@@ -258,6 +375,101 @@ fn wants_detail(message: &str) -> bool {
     triggers.iter().any(|kw| msg.contains(kw))
 }
 
+/// 切分某天的工作段：沿用已保存的模型标题，`generate_titles` 时为其余已结束的工作段请求模型生成标题，结果按天保存
+///
+/// 最后一条记录距今不到空闲间隔的工作段可能还在继续，先用临时标题，结束后再生成
+async fn load_sessions(
+    storage: &StorageManager,
+    config: &Config,
+    date: &str,
+    generate_titles: bool,
+) -> Result<Vec<WorkSession>, String> {
+    let records = storage.get_summaries(date)?;
+    let policy = SessionPolicy::from_config(&config.storage);
+    let mut sessions = Sessionizer::split(&records, &policy);
+    let saved = storage.get_sessions(date).unwrap_or_default();
+    Sessionizer::reuse_titles(&mut sessions, &saved);
+
+    if generate_titles {
+        let closed_before = Local::now().naive_local() - Duration::seconds(policy.idle_seconds);
+        let untitled: Vec<usize> = (0..sessions.len())
+            .filter(|&i| !sessions[i].title_from_model)
+            .filter(|&i| parse_timestamp(&sessions[i].end).map(|end| end < closed_before).unwrap_or(false))
+            .collect();
+        let model_manager = ModelManager::new();
+        for chunk in untitled.chunks(SESSION_TITLE_BATCH) {
+            let batch: Vec<&WorkSession> = chunk.iter().map(|&i| &sessions[i]).collect();
+            let titles = match generate_session_titles(&model_manager, config, &batch).await {
+                Ok(titles) => titles,
+                Err(err) => {
+                    // 保留临时标题，下次查询时重试
+                    eprintln!("生成工作段标题失败: {}", err);
+                    break;
+                }
+            };
+            for (&index, title) in chunk.iter().zip(titles) {
+                sessions[index].title = title;
+                sessions[index].title_from_model = true;
+            }
+        }
+    }
+
+    if sessions != saved {
+        if let Err(err) = storage.save_sessions(date, &sessions) {
+            eprintln!("{}", err);
+        }
+    }
+    Ok(sessions)
+}
+
+/// 一次请求为多个工作段生成标题，按顺序返回；条数对不上时无法确定对应关系，按失败处理
+async fn generate_session_titles(
+    model_manager: &ModelManager,
+    config: &Config,
+    sessions: &[&WorkSession],
+) -> Result<Vec<String>, String> {
+    let mut context = String::from("工作段列表:\n");
+    for (index, session) in sessions.iter().enumerate() {
+        context.push_str(&format!("{}. [{}] 应用: {}", index + 1, session.time_label(), session.apps.join("、")));
+        if !session.project.is_empty() {
+            context.push_str(&format!("，项目: {}", session.project));
+        }
+        if !session.files.is_empty() {
+            context.push_str(&format!("，文件: {}", session.files.join("、")));
+        }
+        context.push_str(&format!("\n   摘要: {}\n", session.highlights.join("；")));
+    }
+
+    let question = format!(
+        "为上面 {} 个工作段各写一个不超过 15 个字的中文标题，概括正在做的任务（如「修复登录接口超时」），不要只写应用名。按顺序每行输出一个标题，共 {} 行，不要编号或其他内容。",
+        sessions.len(),
+        sessions.len()
+    );
+    let reply = model_manager.chat(&config.model, &context, &question).await?;
+
+    let titles: Vec<String> = reply
+        .lines()
+        .map(clean_session_title)
+        .filter(|title| !title.is_empty())
+        .collect();
+    if titles.len() != sessions.len() {
+        return Err(format!("模型返回了 {} 个标题，应为 {} 个", titles.len(), sessions.len()));
+    }
+    Ok(titles)
+}
+
+/// 去掉模型回复中每行的编号、列表符号和引号
+fn clean_session_title(line: &str) -> String {
+    let title = line
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_digit())
+        .trim_start_matches(['.', '、', ')', '）', '-', '*', ':', '：'])
+        .trim()
+        .trim_matches(['"', '“', '”', '「', '」', '《', '》', '*'])
+        .trim();
+    title.chars().take(MAX_SESSION_TITLE_CHARS).collect()
+}
+
 fn merge_recent_records(
     records: Vec<SummaryRecord>,
     fallback: Vec<SummaryRecord>,
@@ -293,6 +505,38 @@ pub async fn get_summaries(date: String) -> Result<Vec<SummaryRecord>, String> {
     storage.get_summaries(&date).map_err(|e| e.to_string())
 }
 
+/// 某天的工作段（按空闲、应用/项目切换和话题变化切分），标题由模型生成后按天保存
+#[tauri::command]
+pub async fn get_sessions(date: String) -> Result<Vec<WorkSession>, String> {
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| format!("日期格式无效: {}", date))?;
+    let storage = StorageManager::new();
+    let config = storage.load_config().map_err(|e| e.to_string())?;
+    load_sessions(&storage, &config, &date, true).await
+}
+
+/// 按应用、活动类型或项目统计用时，附带每天 24 小时的热力图
+///
+/// `range` 为 `today`、`yesterday`、`week`、`month`、`YYYY-MM-DD` 或 `YYYY-MM-DD..YYYY-MM-DD`，
+/// `group_by` 为 `app`（默认）、`activity` 或 `project`
+#[tauri::command]
+pub async fn get_time_stats(range: String, group_by: Option<String>) -> Result<TimeStats, String> {
+    let now = Local::now().naive_local();
+    let range = StatsRange::parse(&range, now.date())?;
+    let group_by = StatsGroup::parse(group_by.as_deref().unwrap_or("app"))?;
+    let storage = StorageManager::new();
+    let config = storage.load_config().map_err(|e| e.to_string())?;
+
+    // 多读前一天的记录，接上跨零点的间隔
+    let mut records: Vec<SummaryRecord> = Vec::new();
+    for date in std::iter::once(range.start - Duration::days(1)).chain(range.dates()) {
+        if let Ok(daily) = storage.get_summaries(&date.format("%Y-%m-%d").to_string()) {
+            records.extend(daily);
+        }
+    }
+    let idle_seconds = config.storage.session_idle_seconds.max(60) as i64;
+    Ok(analysis::compute_time_stats(&records, range, group_by, idle_seconds, now))
+}
+
 #[tauri::command]
 pub async fn clear_summaries(date: String) -> Result<usize, String> {
     let storage = StorageManager::new();
@@ -305,6 +549,20 @@ pub async fn clear_all_summaries() -> Result<usize, String> {
     storage.delete_all_summaries().map_err(|e| e.to_string())
 }
 
+#[derive(serde::Serialize)]
+pub struct ScreenshotCleanupResult {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+/// 删除截图目录中没有被任何记录引用的截图（历史遗留的跳帧截图等）
+#[tauri::command]
+pub async fn cleanup_orphan_screenshots() -> Result<ScreenshotCleanupResult, String> {
+    let storage = StorageManager::new();
+    let (removed, freed_bytes) = storage.cleanup_orphan_screenshots()?;
+    Ok(ScreenshotCleanupResult { removed, freed_bytes })
+}
+
 #[tauri::command]
 pub async fn open_screenshots_dir(app_handle: AppHandle) -> Result<(), String> {
     let storage = StorageManager::new();
@@ -316,6 +574,66 @@ pub async fn open_screenshots_dir(app_handle: AppHandle) -> Result<(), String> {
         .map_err(|e| e.to_string())
 }
 
+/// 读取记录引用的截图，`thumbnail` 为 true 时返回缩略图；以二进制返回，前端收到 ArrayBuffer
+#[tauri::command]
+pub async fn get_screenshot(detail_ref: String, thumbnail: Option<bool>) -> Result<Response, String> {
+    let (bytes, _) = read_screenshot_bytes(&detail_ref, thumbnail.unwrap_or(false))?;
+    Ok(Response::new(bytes))
+}
+
+/// 同 `get_screenshot`，返回可直接用作 `<img src>` 的 data URL
+#[tauri::command]
+pub async fn get_screenshot_data_url(detail_ref: String, thumbnail: Option<bool>) -> Result<String, String> {
+    let (bytes, mime) = read_screenshot_bytes(&detail_ref, thumbnail.unwrap_or(false))?;
+    Ok(format!("data:{};base64,{}", mime, BASE64.encode(bytes)))
+}
+
+fn read_screenshot_bytes(detail_ref: &str, thumbnail: bool) -> Result<(Vec<u8>, &'static str), String> {
+    let storage = StorageManager::new();
+    if thumbnail {
+        Ok((storage.read_thumbnail(detail_ref)?, "image/jpeg"))
+    } else {
+        Ok((storage.read_screenshot(detail_ref)?, screenshot_mime(detail_ref)))
+    }
+}
+
+/// 把时间段内的截图导出为延时回放（GIF / APNG / MJPEG-AVI），返回输出文件信息
+#[tauri::command]
+pub async fn export_timelapse(options: TimelapseOptions) -> Result<TimelapseResult, String> {
+    // 解码和编码都是 CPU 密集操作，放到阻塞线程池执行
+    tokio::task::spawn_blocking(move || {
+        let storage = StorageManager::new();
+        export::export_timelapse(&storage, &options)
+    })
+    .await
+    .map_err(|e| format!("导出任务失败: {}", e))?
+}
+
+/// 用一条分类规则试跑最近几天的记录（默认 7 天），返回命中情况和前后对比，不修改记录
+#[tauri::command]
+pub async fn test_activity_rule(rule: ActivityRule, days: Option<u32>) -> Result<RuleTestResult, String> {
+    let storage = StorageManager::new();
+    let days = days.unwrap_or(7).clamp(1, 365);
+    let today = Local::now().date_naive();
+
+    let mut records: Vec<SummaryRecord> = Vec::new();
+    for offset in (0..days as i64).rev() {
+        let date = (today - Duration::days(offset)).format("%Y-%m-%d").to_string();
+        if let Ok(daily) = storage.get_summaries(&date) {
+            records.extend(daily);
+        }
+    }
+    analysis::test_activity_rule(&rule, &records)
+}
+
+/// 在最近几天（默认 7 天）的屏幕文字中精确查找，返回命中的行，最新的在前
+#[tauri::command]
+pub async fn search_screen_text(text: String, days: Option<u32>) -> Result<Vec<ScreenTextMatch>, String> {
+    let storage = StorageManager::new();
+    let days = days.unwrap_or(7).clamp(1, 365);
+    Ok(storage.search_screen_text(&text, days, 50))
+}
+
 #[derive(serde::Serialize)]
 pub struct AlertRecord {
     pub timestamp: String,
@@ -323,6 +641,7 @@ pub struct AlertRecord {
     pub message: String,
     pub suggestion: String,
     pub confidence: f32,
+    pub issue: Option<IssueInfo>,
 }
 
 #[tauri::command]
@@ -376,7 +695,11 @@ pub async fn get_recent_alerts(since: Option<String>) -> Result<Vec<AlertRecord>
         } else {
             record.issue_summary.clone()
         };
-        let key = format!("{}:{}", record.issue_type, message);
+        let key = record
+            .issue
+            .as_ref()
+            .and_then(IssueInfo::dedupe_key)
+            .unwrap_or_else(|| format!("{}:{}", record.issue_type, message));
         if let Some(prev) = last_seen.get(&key) {
             if dt.signed_duration_since(*prev).num_seconds() < cooldown {
                 continue;
@@ -394,6 +717,7 @@ pub async fn get_recent_alerts(since: Option<String>) -> Result<Vec<AlertRecord>
             message,
             suggestion: record.suggestion,
             confidence: record.confidence,
+            issue: record.issue,
         });
     }
 
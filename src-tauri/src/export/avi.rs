@@ -0,0 +1,173 @@
+use std::io::{Seek, SeekFrom, Write};
+
+/// avih 中的 AVIF_HASINDEX 标志
+const AVIF_HASINDEX: u32 = 0x10;
+/// idx1 中的关键帧标志（MJPEG 每一帧都是关键帧）
+const AVIIF_KEYFRAME: u32 = 0x10;
+
+/// MJPEG-in-AVI 写入器：每帧一张 JPEG，结束时回填帧数和各块大小并写入索引
+pub struct MjpegAviWriter<W: Write + Seek> {
+    out: W,
+    /// 'movi' 标识所在位置，idx1 中的偏移相对于它
+    movi_pos: u64,
+    /// (相对 movi 的偏移, 数据大小)
+    index: Vec<(u32, u32)>,
+    max_frame_size: u32,
+}
+
+/// 头部中需要在结束时回填的字段位置
+const RIFF_SIZE_POS: u64 = 4;
+const AVIH_TOTAL_FRAMES_POS: u64 = 48;
+const AVIH_BUFFER_SIZE_POS: u64 = 60;
+const STRH_LENGTH_POS: u64 = 140;
+const STRH_BUFFER_SIZE_POS: u64 = 144;
+const MOVI_SIZE_POS: u64 = 216;
+
+impl<W: Write + Seek> MjpegAviWriter<W> {
+    pub fn new(mut out: W, width: u32, height: u32, fps: f32) -> Result<Self, String> {
+        // 帧率用 rate/scale 表示，支持小数帧率
+        let scale = 1000u32;
+        let rate = (fps * scale as f32).round().max(1.0) as u32;
+        let micro_sec_per_frame = (1_000_000.0 / fps).round() as u32;
+
+        let mut header = Vec::with_capacity(224);
+        header.extend_from_slice(b"RIFF");
+        put_u32(&mut header, 0); // 文件大小，结束时回填
+        header.extend_from_slice(b"AVI ");
+
+        header.extend_from_slice(b"LIST");
+        put_u32(&mut header, 192);
+        header.extend_from_slice(b"hdrl");
+
+        header.extend_from_slice(b"avih");
+        put_u32(&mut header, 56);
+        put_u32(&mut header, micro_sec_per_frame);
+        put_u32(&mut header, 0); // dwMaxBytesPerSec
+        put_u32(&mut header, 0); // dwPaddingGranularity
+        put_u32(&mut header, AVIF_HASINDEX);
+        put_u32(&mut header, 0); // dwTotalFrames，回填
+        put_u32(&mut header, 0); // dwInitialFrames
+        put_u32(&mut header, 1); // dwStreams
+        put_u32(&mut header, 0); // dwSuggestedBufferSize，回填
+        put_u32(&mut header, width);
+        put_u32(&mut header, height);
+        for _ in 0..4 {
+            put_u32(&mut header, 0);
+        }
+
+        header.extend_from_slice(b"LIST");
+        put_u32(&mut header, 116);
+        header.extend_from_slice(b"strl");
+
+        header.extend_from_slice(b"strh");
+        put_u32(&mut header, 56);
+        header.extend_from_slice(b"vids");
+        header.extend_from_slice(b"MJPG");
+        put_u32(&mut header, 0); // dwFlags
+        put_u32(&mut header, 0); // wPriority + wLanguage
+        put_u32(&mut header, 0); // dwInitialFrames
+        put_u32(&mut header, scale);
+        put_u32(&mut header, rate);
+        put_u32(&mut header, 0); // dwStart
+        put_u32(&mut header, 0); // dwLength，回填
+        put_u32(&mut header, 0); // dwSuggestedBufferSize，回填
+        put_u32(&mut header, u32::MAX); // dwQuality
+        put_u32(&mut header, 0); // dwSampleSize
+        put_u16(&mut header, 0);
+        put_u16(&mut header, 0);
+        put_u16(&mut header, width.min(u16::MAX as u32) as u16);
+        put_u16(&mut header, height.min(u16::MAX as u32) as u16);
+
+        header.extend_from_slice(b"strf");
+        put_u32(&mut header, 40);
+        put_u32(&mut header, 40); // biSize
+        put_u32(&mut header, width);
+        put_u32(&mut header, height);
+        put_u16(&mut header, 1); // biPlanes
+        put_u16(&mut header, 24); // biBitCount
+        header.extend_from_slice(b"MJPG");
+        put_u32(&mut header, width * height * 3);
+        for _ in 0..4 {
+            put_u32(&mut header, 0);
+        }
+
+        header.extend_from_slice(b"LIST");
+        put_u32(&mut header, 0); // movi 大小，回填
+        header.extend_from_slice(b"movi");
+        debug_assert_eq!(header.len(), 224);
+
+        out.write_all(&header).map_err(write_error)?;
+
+        Ok(Self {
+            out,
+            movi_pos: MOVI_SIZE_POS + 4,
+            index: Vec::new(),
+            max_frame_size: 0,
+        })
+    }
+
+    /// 写入一帧 JPEG 数据
+    pub fn write_frame(&mut self, jpeg: &[u8]) -> Result<(), String> {
+        let position = self.out.stream_position().map_err(write_error)?;
+        let size = jpeg.len() as u32;
+
+        self.out.write_all(b"00dc").map_err(write_error)?;
+        self.out.write_all(&size.to_le_bytes()).map_err(write_error)?;
+        self.out.write_all(jpeg).map_err(write_error)?;
+        // 块按 2 字节对齐
+        if size % 2 == 1 {
+            self.out.write_all(&[0]).map_err(write_error)?;
+        }
+
+        self.index.push(((position - self.movi_pos) as u32, size));
+        self.max_frame_size = self.max_frame_size.max(size);
+        Ok(())
+    }
+
+    /// 写入索引并回填头部，返回写入的帧数
+    pub fn finish(mut self) -> Result<usize, String> {
+        let movi_end = self.out.stream_position().map_err(write_error)?;
+
+        let mut idx1 = Vec::with_capacity(8 + self.index.len() * 16);
+        idx1.extend_from_slice(b"idx1");
+        put_u32(&mut idx1, (self.index.len() * 16) as u32);
+        for (offset, size) in &self.index {
+            idx1.extend_from_slice(b"00dc");
+            put_u32(&mut idx1, AVIIF_KEYFRAME);
+            put_u32(&mut idx1, *offset);
+            put_u32(&mut idx1, *size);
+        }
+        self.out.write_all(&idx1).map_err(write_error)?;
+        let file_end = self.out.stream_position().map_err(write_error)?;
+
+        let frames = self.index.len() as u32;
+        let patches = [
+            (RIFF_SIZE_POS, (file_end - 8) as u32),
+            (AVIH_TOTAL_FRAMES_POS, frames),
+            (AVIH_BUFFER_SIZE_POS, self.max_frame_size + 8),
+            (STRH_LENGTH_POS, frames),
+            (STRH_BUFFER_SIZE_POS, self.max_frame_size + 8),
+            (MOVI_SIZE_POS, (movi_end - MOVI_SIZE_POS - 4) as u32),
+        ];
+        for (position, value) in patches {
+            self.out.seek(SeekFrom::Start(position)).map_err(write_error)?;
+            self.out.write_all(&value.to_le_bytes()).map_err(write_error)?;
+        }
+        self.out.seek(SeekFrom::End(0)).map_err(write_error)?;
+        self.out.flush().map_err(write_error)?;
+
+        Ok(self.index.len())
+    }
+}
+
+fn put_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_error(err: std::io::Error) -> String {
+    format!("写入视频失败: {}", err)
+}
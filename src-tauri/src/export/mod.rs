@@ -0,0 +1,7 @@
+pub mod avi;
+pub mod overlay;
+pub mod timelapse;
+
+pub use avi::*;
+pub use overlay::*;
+pub use timelapse::*;
@@ -0,0 +1,141 @@
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::{Rgb, RgbImage};
+use std::fs;
+use std::path::Path;
+
+/// 常见系统中带中文字形的字体，按顺序尝试
+const FONT_CANDIDATES: &[&str] = &[
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\msyh.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+];
+
+/// 在画面底部叠加文字（时间、摘要）
+pub struct TextOverlay {
+    font: FontVec,
+}
+
+impl TextOverlay {
+    /// 加载指定字体；未指定时依次尝试常见系统字体
+    pub fn load(font_path: Option<&str>) -> Result<Self, String> {
+        if let Some(path) = font_path.filter(|p| !p.trim().is_empty()) {
+            return load_font(Path::new(path.trim()))
+                .map(|font| Self { font })
+                .ok_or_else(|| format!("无法加载字体: {}", path));
+        }
+
+        FONT_CANDIDATES
+            .iter()
+            .find_map(|path| load_font(Path::new(path)))
+            .map(|font| Self { font })
+            .ok_or_else(|| "未找到可用的中文字体，请通过 font_path 指定字体文件".to_string())
+    }
+
+    /// 在画面底部画一条半透明底栏，逐行写入文字，超出宽度的部分用省略号截断
+    pub fn draw(&self, image: &mut RgbImage, lines: &[String]) {
+        let lines: Vec<&str> = lines
+            .iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        if lines.is_empty() {
+            return;
+        }
+
+        let font_px = (image.height() as f32 / 32.0).clamp(12.0, 48.0);
+        let scaled = self.font.as_scaled(PxScale::from(font_px));
+        let line_height = (scaled.ascent() - scaled.descent() + scaled.line_gap()).ceil();
+        let padding = (font_px / 3.0).ceil();
+        let bar_height = (line_height * lines.len() as f32 + padding * 2.0) as u32;
+        let bar_top = image.height().saturating_sub(bar_height);
+
+        for y in bar_top..image.height() {
+            for x in 0..image.width() {
+                let pixel = image.get_pixel_mut(x, y);
+                for channel in pixel.0.iter_mut() {
+                    *channel = (*channel as f32 * 0.35) as u8;
+                }
+            }
+        }
+
+        let max_width = image.width() as f32 - padding * 2.0;
+        for (index, line) in lines.iter().enumerate() {
+            let text = fit_line(&scaled, line, max_width);
+            let baseline = bar_top as f32 + padding + line_height * index as f32 + scaled.ascent();
+            self.draw_line(image, &text, padding, baseline, font_px);
+        }
+    }
+
+    fn draw_line(&self, image: &mut RgbImage, text: &str, left: f32, baseline: f32, font_px: f32) {
+        let scale = PxScale::from(font_px);
+        let scaled = self.font.as_scaled(scale);
+        let (width, height) = (image.width() as i32, image.height() as i32);
+
+        let mut caret = left;
+        for ch in text.chars() {
+            let id = self.font.glyph_id(ch);
+            let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+            caret += scaled.h_advance(id);
+
+            let outlined = match self.font.outline_glyph(glyph) {
+                Some(outlined) => outlined,
+                None => continue,
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let x = bounds.min.x as i32 + gx as i32;
+                let y = bounds.min.y as i32 + gy as i32;
+                if x < 0 || y < 0 || x >= width || y >= height {
+                    return;
+                }
+                let pixel = image.get_pixel_mut(x as u32, y as u32);
+                *pixel = blend(*pixel, coverage.clamp(0.0, 1.0));
+            });
+        }
+    }
+}
+
+fn load_font(path: &Path) -> Option<FontVec> {
+    let data = fs::read(path).ok()?;
+    FontVec::try_from_vec_and_index(data, 0).ok()
+}
+
+/// 截断到指定宽度，超出时末尾加省略号
+fn fit_line<F: Font, SF: ScaleFont<F>>(scaled: &SF, text: &str, max_width: f32) -> String {
+    let ellipsis_width = scaled.h_advance(scaled.glyph_id('…'));
+    let mut width = 0.0;
+    let mut fitted = String::new();
+
+    for ch in text.chars() {
+        let ch = if ch == '\n' || ch == '\r' { ' ' } else { ch };
+        let advance = scaled.h_advance(scaled.glyph_id(ch));
+        if width + advance > max_width {
+            while width + ellipsis_width > max_width {
+                match fitted.pop() {
+                    Some(last) => width -= scaled.h_advance(scaled.glyph_id(last)),
+                    None => break,
+                }
+            }
+            fitted.push('…');
+            return fitted;
+        }
+        width += advance;
+        fitted.push(ch);
+    }
+    fitted
+}
+
+/// 按覆盖率把白色文字混合到底色上
+fn blend(base: Rgb<u8>, coverage: f32) -> Rgb<u8> {
+    let mix = |channel: u8| (channel as f32 + (255.0 - channel as f32) * coverage) as u8;
+    Rgb([mix(base.0[0]), mix(base.0[1]), mix(base.0[2])])
+}
@@ -0,0 +1,330 @@
+use super::{MjpegAviWriter, TextOverlay};
+use crate::storage::{StorageManager, SummaryRecord};
+use chrono::{Duration, NaiveDateTime};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{Delay, DynamicImage, Frame, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+/// 单次导出的最大帧数，超出时在时间段内均匀抽帧
+const MAX_TIMELAPSE_FRAMES: usize = 3000;
+/// MJPEG 每帧的 JPEG 质量
+const VIDEO_JPEG_QUALITY: u8 = 85;
+
+/// 延时回放导出参数
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimelapseOptions {
+    /// 起止时间，格式 `YYYY-MM-DDTHH:MM[:SS]`
+    pub start: String,
+    pub end: String,
+    /// `gif`、`apng` 或 `avi`（MJPEG）
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// 每秒帧数
+    #[serde(default = "default_fps")]
+    pub fps: f32,
+    /// 输出画面的最大宽高，0 表示不限制；画面按比例缩放
+    #[serde(default = "default_max_width")]
+    pub max_width: u32,
+    #[serde(default)]
+    pub max_height: u32,
+    /// 在画面底部叠加时间
+    #[serde(default)]
+    pub overlay_time: bool,
+    /// 在画面底部叠加摘要
+    #[serde(default)]
+    pub overlay_summary: bool,
+    /// 叠加文字使用的字体文件，未指定时使用系统字体
+    #[serde(default)]
+    pub font_path: Option<String>,
+    /// 输出文件路径，未指定时写入数据目录下的 exports/
+    #[serde(default)]
+    pub output_path: Option<String>,
+}
+
+fn default_format() -> String {
+    "gif".to_string()
+}
+
+fn default_fps() -> f32 {
+    2.0
+}
+
+fn default_max_width() -> u32 {
+    1280
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelapseResult {
+    pub path: String,
+    pub format: String,
+    pub frame_count: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 一帧对应的记录信息
+struct TimelapseEntry {
+    timestamp: String,
+    summary: String,
+    detail_ref: String,
+}
+
+/// 把时间段内记录引用的截图按时间顺序导出为动画或视频
+///
+/// 连续引用同一张截图的记录只取第一条；截图缺失的记录跳过
+pub fn export_timelapse(storage: &StorageManager, options: &TimelapseOptions) -> Result<TimelapseResult, String> {
+    let format = options.format.trim().to_lowercase();
+    let extension = match format.as_str() {
+        "gif" => "gif",
+        "apng" => "png",
+        "avi" | "mjpeg" => "avi",
+        _ => return Err(format!("不支持的导出格式: {}", options.format)),
+    };
+    if !options.fps.is_finite() || options.fps <= 0.0 {
+        return Err("帧率必须大于 0".to_string());
+    }
+    let fps = options.fps.clamp(0.1, 60.0);
+
+    let start = parse_time(&options.start)?;
+    let end = parse_time(&options.end)?;
+    if end <= start {
+        return Err("结束时间必须晚于开始时间".to_string());
+    }
+
+    let entries = collect_entries(storage, start, end);
+    if entries.is_empty() {
+        return Err("该时间段内没有可导出的截图".to_string());
+    }
+
+    let overlay = if options.overlay_time || options.overlay_summary {
+        Some(TextOverlay::load(options.font_path.as_deref())?)
+    } else {
+        None
+    };
+
+    let first = load_image(storage, &entries[0].detail_ref)?;
+    let (width, height) = canvas_size(first.width(), first.height(), options.max_width, options.max_height);
+
+    let path = match options.output_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => storage.exports_dir()?.join(format!(
+            "timelapse-{}-{}.{}",
+            start.format("%Y%m%d-%H%M%S"),
+            end.format("%Y%m%d-%H%M%S"),
+            extension
+        )),
+    };
+    let file = File::create(&path).map_err(|e| format!("创建导出文件失败 {:?}: {}", path, e))?;
+    let writer = BufWriter::new(file);
+
+    let mut sink = match extension {
+        "gif" => FrameSink::gif(writer)?,
+        "png" => FrameSink::apng(writer, width, height, entries.len(), fps)?,
+        _ => FrameSink::Avi(MjpegAviWriter::new(writer, width, height, fps)?),
+    };
+    let delay = Delay::from_numer_denom_ms((1000.0 / fps).round() as u32, 1);
+
+    let mut previous: Option<RgbImage> = None;
+    for (index, entry) in entries.iter().enumerate() {
+        let image = if index == 0 {
+            Ok(first.clone())
+        } else {
+            load_image(storage, &entry.detail_ref)
+        };
+        let mut frame = match image {
+            Ok(image) => fit_to_canvas(&image, width, height),
+            // 读取失败时重复上一帧，保持 APNG 声明的帧数和时间轴不变
+            Err(err) => {
+                eprintln!("读取截图失败 {}: {}", entry.detail_ref, err);
+                previous.clone().unwrap_or_else(|| RgbImage::new(width, height))
+            }
+        };
+
+        if let Some(overlay) = &overlay {
+            let mut lines = Vec::new();
+            if options.overlay_time {
+                lines.push(entry.timestamp.replacen('T', " ", 1));
+            }
+            if options.overlay_summary {
+                lines.push(entry.summary.clone());
+            }
+            overlay.draw(&mut frame, &lines);
+        }
+
+        sink.write(&frame, delay)?;
+        previous = Some(frame);
+    }
+    sink.finish()?;
+
+    Ok(TimelapseResult {
+        path: path.to_string_lossy().to_string(),
+        format,
+        frame_count: entries.len(),
+        width,
+        height,
+    })
+}
+
+/// 不同格式的帧写入方式
+enum FrameSink {
+    Gif(GifEncoder<BufWriter<File>>),
+    Apng(png::Writer<BufWriter<File>>),
+    Avi(MjpegAviWriter<BufWriter<File>>),
+}
+
+impl FrameSink {
+    fn gif(writer: BufWriter<File>) -> Result<Self, String> {
+        // 量化速度 10：兼顾色彩质量和导出耗时
+        let mut encoder = GifEncoder::new_with_speed(writer, 10);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("写入 GIF 失败: {}", e))?;
+        Ok(FrameSink::Gif(encoder))
+    }
+
+    fn apng(writer: BufWriter<File>, width: u32, height: u32, frames: usize, fps: f32) -> Result<Self, String> {
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames as u32, 0)
+            .map_err(|e| format!("写入 APNG 失败: {}", e))?;
+        let delay_ms = (1000.0 / fps).round().clamp(1.0, u16::MAX as f32) as u16;
+        encoder
+            .set_frame_delay(delay_ms, 1000)
+            .map_err(|e| format!("写入 APNG 失败: {}", e))?;
+        let writer = encoder
+            .write_header()
+            .map_err(|e| format!("写入 APNG 失败: {}", e))?;
+        Ok(FrameSink::Apng(writer))
+    }
+
+    fn write(&mut self, frame: &RgbImage, delay: Delay) -> Result<(), String> {
+        match self {
+            FrameSink::Gif(encoder) => {
+                let rgba = DynamicImage::ImageRgb8(frame.clone()).to_rgba8();
+                encoder
+                    .encode_frame(Frame::from_parts(rgba, 0, 0, delay))
+                    .map_err(|e| format!("写入 GIF 失败: {}", e))
+            }
+            FrameSink::Apng(writer) => writer
+                .write_image_data(frame.as_raw())
+                .map_err(|e| format!("写入 APNG 失败: {}", e)),
+            FrameSink::Avi(writer) => {
+                let mut jpeg = Vec::new();
+                JpegEncoder::new_with_quality(&mut jpeg, VIDEO_JPEG_QUALITY)
+                    .encode_image(frame)
+                    .map_err(|e| format!("编码视频帧失败: {}", e))?;
+                writer.write_frame(&jpeg)
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            // GIF 编码器在析构时写入结尾
+            FrameSink::Gif(encoder) => {
+                drop(encoder);
+                Ok(())
+            }
+            FrameSink::Apng(writer) => writer.finish().map_err(|e| format!("写入 APNG 失败: {}", e)),
+            FrameSink::Avi(writer) => writer.finish().map(|_| ()),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Result<NaiveDateTime, String> {
+    let value = value.trim();
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+        .map_err(|_| format!("时间格式无效: {}", value))
+}
+
+/// 按时间顺序收集时间段内带截图的记录
+fn collect_entries(storage: &StorageManager, start: NaiveDateTime, end: NaiveDateTime) -> Vec<TimelapseEntry> {
+    let from = start.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let until = end.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    let mut records: Vec<SummaryRecord> = Vec::new();
+    let mut date = start.date();
+    while date <= end.date() {
+        if let Ok(daily) = storage.get_summaries(&date.format("%Y-%m-%d").to_string()) {
+            records.extend(
+                daily
+                    .into_iter()
+                    .filter(|r| r.timestamp >= from && r.timestamp <= until && !r.detail_ref.is_empty()),
+            );
+        }
+        date += Duration::days(1);
+    }
+    records.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    let mut entries: Vec<TimelapseEntry> = Vec::new();
+    for record in records {
+        if entries.last().map(|e| e.detail_ref == record.detail_ref).unwrap_or(false) {
+            continue;
+        }
+        if storage.screenshot_path(&record.detail_ref).is_err() {
+            continue;
+        }
+        entries.push(TimelapseEntry {
+            timestamp: record.timestamp,
+            summary: record.summary,
+            detail_ref: record.detail_ref,
+        });
+    }
+
+    if entries.len() > MAX_TIMELAPSE_FRAMES {
+        let step = entries.len() as f64 / MAX_TIMELAPSE_FRAMES as f64;
+        let mut sampled = Vec::with_capacity(MAX_TIMELAPSE_FRAMES);
+        for (index, entry) in entries.into_iter().enumerate() {
+            if (index as f64 / step).floor() as usize >= sampled.len() {
+                sampled.push(entry);
+            }
+        }
+        entries = sampled;
+    }
+    entries
+}
+
+fn load_image(storage: &StorageManager, detail_ref: &str) -> Result<DynamicImage, String> {
+    let bytes = storage.read_screenshot(detail_ref)?;
+    image::load_from_memory(&bytes).map_err(|e| format!("解析截图失败: {}", e))
+}
+
+/// 按第一帧的比例计算输出尺寸；宽高取偶数，兼容常见视频播放器
+fn canvas_size(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let mut scale = 1.0f64;
+    if max_width > 0 && width > max_width {
+        scale = scale.min(max_width as f64 / width as f64);
+    }
+    if max_height > 0 && height > max_height {
+        scale = scale.min(max_height as f64 / height as f64);
+    }
+
+    let even = |value: f64| ((value.round() as u32) / 2 * 2).max(2);
+    (even(width as f64 * scale), even(height as f64 * scale))
+}
+
+/// 等比缩放到画布内并居中，其余部分填黑（多显示器或分辨率变化时尺寸不一致）
+fn fit_to_canvas(image: &DynamicImage, width: u32, height: u32) -> RgbImage {
+    let resized = if image.width() == width && image.height() == height {
+        image.to_rgb8()
+    } else {
+        image.resize(width, height, FilterType::Triangle).to_rgb8()
+    };
+    if resized.width() == width && resized.height() == height {
+        return resized;
+    }
+
+    let mut canvas = RgbImage::new(width, height);
+    let x = (width - resized.width()) / 2;
+    let y = (height - resized.height()) / 2;
+    image::imageops::replace(&mut canvas, &resized, x as i64, y as i64);
+    canvas
+}
@@ -0,0 +1,58 @@
+use crate::capture::{CaptureManager, ConsoleSink, ReplaySource};
+use crate::storage::StorageManager;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// 无界面回放：`screen-assistant --replay [目录] [--speed 倍速]`
+///
+/// 不启动窗口，把目录中的截图（默认为截图目录）走一遍完整的分析流程，提醒和模型错误打印到终端，
+/// 记录照常写入数据目录。默认倍速为 0，即不等待、尽快回放；Ctrl+C 停止时已排队的分析完成后再退出
+pub fn run_headless_replay(args: &[String]) -> Result<(), String> {
+    let mut dir: Option<PathBuf> = None;
+    let mut speed = 0.0;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--speed" => {
+                let value = args.next().ok_or("--speed 缺少倍速")?;
+                speed = value.parse().map_err(|_| format!("无效的倍速: {}", value))?;
+            }
+            other if dir.is_none() && !other.starts_with("--") => dir = Some(PathBuf::from(other)),
+            other => return Err(format!("无法识别的参数: {}", other)),
+        }
+    }
+
+    let storage = StorageManager::new();
+    let config = storage.load_config()?;
+    let dir = match dir {
+        Some(dir) => dir,
+        None => storage.screenshots_dir()?,
+    };
+    let source = ReplaySource::new(&dir, speed)?;
+    println!("回放 {} 帧: {}", source.remaining(), dir.display());
+
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("启动运行时失败: {}", e))?;
+    runtime.block_on(async move {
+        let mut manager = CaptureManager::new();
+        manager.start_with_source(config, Arc::new(ConsoleSink), Box::new(source)).await;
+
+        let finished = tokio::select! {
+            _ = manager.wait() => true,
+            _ = tokio::signal::ctrl_c() => false,
+        };
+        if !finished {
+            manager.stop().await;
+            manager.wait().await;
+        }
+
+        let status = manager.status();
+        println!(
+            "回放结束：写入 {} 条记录，跳过 {} 帧，失败 {} 帧",
+            status.record_count, status.skipped_count, status.failed_count
+        );
+        match status.last_error {
+            Some(err) if status.failed_count > 0 => Err(format!("最近一次错误: {}", err)),
+            _ => Ok(()),
+        }
+    })
+}
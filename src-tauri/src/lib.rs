@@ -4,17 +4,22 @@ mod model;
 mod storage;
 mod analysis;
 mod assistant;
+mod export;
+mod headless;
 
 use crate::storage::StorageManager;
+pub use headless::run_headless_replay;
 use commands::{
     AppState,
     get_config, save_config, list_profiles, save_profile, load_profile, delete_profile,
     test_model_connection,
-    start_capture, stop_capture, get_capture_status,
-    chat_with_assistant, get_summaries,
+    start_capture, start_replay, stop_capture, pause_capture, resume_capture,
+    get_capture_status, list_displays,
+    chat_with_assistant, ask_screen, get_summaries, get_sessions, get_time_stats,
     get_recent_alerts,
     clear_summaries, clear_all_summaries,
-    open_screenshots_dir,
+    open_screenshots_dir, cleanup_orphan_screenshots,
+    get_screenshot, get_screenshot_data_url, export_timelapse, test_activity_rule, search_screen_text,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -40,14 +45,27 @@ pub fn run() {
             delete_profile,
             test_model_connection,
             start_capture,
+            start_replay,
             stop_capture,
+            pause_capture,
+            resume_capture,
             get_capture_status,
+            list_displays,
             chat_with_assistant,
+            ask_screen,
             get_summaries,
+            get_sessions,
+            get_time_stats,
             get_recent_alerts,
             clear_summaries,
             clear_all_summaries,
             open_screenshots_dir,
+            cleanup_orphan_screenshots,
+            get_screenshot,
+            get_screenshot_data_url,
+            export_timelapse,
+            test_activity_rule,
+            search_screen_text,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -214,27 +214,30 @@ impl ApiClient {
             .map(|c| c.message.content.clone())
             .ok_or_else(|| "没有返回内容".to_string())
     }
-    pub async fn analyze_image(&self, image_base64: &str, prompt: &str) -> Result<String, String> {
+    /// 图片分析，多张图片按顺序附在同一条消息中
+    pub async fn analyze_images(&self, images_base64: &[&str], prompt: &str) -> Result<String, String> {
         let url = format!("{}/chat/completions", self.config.endpoint);
 
+        let mut parts = vec![ContentPart {
+            content_type: "text".to_string(),
+            text: Some(prompt.to_string()),
+            image_url: None,
+        }];
+        for image_base64 in images_base64 {
+            parts.push(ContentPart {
+                content_type: "image_url".to_string(),
+                text: None,
+                image_url: Some(ImageUrl {
+                    url: format!("data:image/jpeg;base64,{}", image_base64),
+                }),
+            });
+        }
+
         let request = ChatRequest {
             model: self.config.model.clone(),
             messages: vec![Message {
                 role: "user".to_string(),
-                content: MessageContent::Parts(vec![
-                    ContentPart {
-                        content_type: "text".to_string(),
-                        text: Some(prompt.to_string()),
-                        image_url: None,
-                    },
-                    ContentPart {
-                        content_type: "image_url".to_string(),
-                        text: None,
-                        image_url: Some(ImageUrl {
-                            url: format!("data:image/jpeg;base64,{}", image_base64),
-                        }),
-                    },
-                ]),
+                content: MessageContent::Parts(parts),
             }],
             max_tokens: 10000,
         };
@@ -350,7 +353,10 @@ fn write_exchange_log(
         log.push('\n');
     }
 
-    if let Err(err) = StorageManager::new().write_log_snapshot(prefix, &log) {
+    let storage = StorageManager::new();
+    let log = super::scrub_exchange_log(&storage, &log);
+
+    if let Err(err) = storage.write_log_snapshot(prefix, &log) {
         eprintln!("写入日志失败: {}", err);
     }
 }
@@ -7,8 +7,33 @@ pub use api::*;
 pub use error::*;
 pub use ollama::*;
 
-use crate::storage::ModelConfig;
+use crate::analysis::Scrubber;
+use crate::storage::{ModelConfig, StorageManager};
 use crate::commands::ChatHistoryMessage;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// 写请求日志用的脱敏规则，按 config.json 的修改时间缓存，避免每次请求都读配置、编译自定义规则
+static LOG_SCRUBBER: Mutex<Option<(Option<SystemTime>, Arc<Scrubber>)>> = parking_lot::const_mutex(None);
+
+/// 请求日志同样要脱敏；图片数据原样保留
+fn scrub_exchange_log(storage: &StorageManager, log: &str) -> String {
+    let modified = storage.config_modified_time();
+    let scrubber = {
+        let mut cached = LOG_SCRUBBER.lock();
+        match cached.as_ref() {
+            Some((at, scrubber)) if *at == modified => scrubber.clone(),
+            _ => {
+                let config = storage.load_config().unwrap_or_default();
+                let scrubber = Arc::new(Scrubber::from_config(&config.capture));
+                *cached = Some((modified, scrubber.clone()));
+                scrubber
+            }
+        }
+    };
+    scrubber.scrub_log(log)
+}
 
 pub struct ModelManager;
 
@@ -89,20 +114,21 @@ impl ModelManager {
             _ => Err("未知的模型提供者".to_string()),
         }
     }
-    pub async fn analyze_image(
+    /// 一次请求分析多张图片（按顺序附在同一条消息中）
+    pub async fn analyze_images(
         &self,
         config: &ModelConfig,
-        image_base64: &str,
+        images_base64: &[&str],
         prompt: &str,
     ) -> Result<String, String> {
         match config.provider.as_str() {
             "api" => {
                 let api_client = ApiClient::new(&config.api);
-                api_client.analyze_image(image_base64, prompt).await
+                api_client.analyze_images(images_base64, prompt).await
             }
             "ollama" => {
                 let ollama_client = OllamaClient::new(&config.ollama);
-                ollama_client.analyze_image(image_base64, prompt).await
+                ollama_client.analyze_images(images_base64, prompt).await
             }
             _ => Err("未知的模型提供者".to_string()),
         }
@@ -177,14 +177,15 @@ impl OllamaClient {
 
         Ok(generate_response.response)
     }
-    pub async fn analyze_image(&self, image_base64: &str, prompt: &str) -> Result<String, String> {
+    /// 图片分析，多张图片按顺序放入 images
+    pub async fn analyze_images(&self, images_base64: &[&str], prompt: &str) -> Result<String, String> {
         let url = format!("{}/api/generate", self.config.endpoint);
 
         let request = GenerateRequest {
             model: self.config.model.clone(),
             prompt: prompt.to_string(),
             system: None,
-            images: Some(vec![image_base64.to_string()]),
+            images: Some(images_base64.iter().map(|image| image.to_string()).collect()),
             stream: false,
         };
 
@@ -246,7 +247,10 @@ fn write_exchange_log(
         log.push('\n');
     }
 
-    if let Err(err) = StorageManager::new().write_log_snapshot(prefix, &log) {
+    let storage = StorageManager::new();
+    let log = super::scrub_exchange_log(&storage, &log);
+
+    if let Err(err) = storage.write_log_snapshot(prefix, &log) {
         eprintln!("写入日志失败: {}", err);
     }
 }
@@ -9,6 +9,6 @@ pub trait ModelProvider: Send + Sync {
     /// 文本对话
     async fn chat(&self, system_prompt: &str, user_message: &str) -> Result<String, String>;
 
-    /// 图片分析
-    async fn analyze_image(&self, image_base64: &str, prompt: &str) -> Result<String, String>;
+    /// 图片分析，支持一次传入多张图片
+    async fn analyze_images(&self, images_base64: &[&str], prompt: &str) -> Result<String, String>;
 }
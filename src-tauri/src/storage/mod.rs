@@ -1,8 +1,28 @@
-use chrono::{DateTime, Local, Duration, Timelike};
+use crate::analysis::{DiffAnalyzer, IssueInfo, Tokenizer, WorkSession};
+use chrono::{DateTime, Local, Duration, NaiveDateTime, Timelike};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
-use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::time::SystemTime;
+
+/// 截图内容索引文件（内容指纹 -> 文件名），每行一条，新条目追加写入
+const SCREENSHOT_INDEX_FILE: &str = "index.jsonl";
+/// 旧版本整体读写的索引文件，首次加载时并入新索引
+const LEGACY_SCREENSHOT_INDEX_FILE: &str = "index.json";
+/// 清理孤立截图时跳过最近写入的文件，避免误删尚未写入记录的截图
+const ORPHAN_GRACE_SECONDS: u64 = 120;
+/// 缩略图子目录（位于截图目录下）
+const THUMBNAIL_DIR: &str = "thumbs";
+/// 缩略图最长边像素
+const THUMBNAIL_SIZE: u32 = 320;
+
+/// 每日记录文件的读-改-写都在此锁内完成；截屏任务写入的状态记录、分析任务提交的记录
+/// 和删除操作可能同时发生，不加锁时后写入的一方会覆盖另一方的修改
+static SUMMARY_FILE_LOCK: parking_lot::Mutex<()> = parking_lot::const_mutex(());
+/// 常驻内存的截图索引，首次使用时从磁盘加载
+static SCREENSHOT_INDEX: parking_lot::Mutex<Option<ScreenshotIndex>> = parking_lot::const_mutex(None);
 
 // ============ 配置结构 ============
 
@@ -52,6 +72,94 @@ pub struct CaptureConfig {
     pub alert_confidence_threshold: f32,  // issue 提醒触发阈值
     #[serde(default = "default_alert_cooldown_seconds")]
     pub alert_cooldown_seconds: u64,  // issue 提醒冷却时间（秒）
+    #[serde(default = "default_capture_mode")]
+    pub capture_mode: String,  // 截屏模式: primary / display / each / stitched
+    #[serde(default)]
+    pub display_id: Option<u32>,  // capture_mode 为 display 时使用的显示器 id
+    #[serde(default = "default_upload_max_width")]
+    pub upload_max_width: u32,  // 上传给模型的最大宽度，0 表示不限制
+    #[serde(default = "default_upload_max_height")]
+    pub upload_max_height: u32,  // 上传给模型的最大高度，0 表示不限制
+    #[serde(default)]
+    pub save_quality: Option<u8>,  // 本地截图 JPEG 质量，未设置时沿用 compress_quality
+    #[serde(default)]
+    pub save_max_width: u32,  // 本地截图最大宽度，0 表示不限制
+    #[serde(default)]
+    pub save_max_height: u32,  // 本地截图最大高度，0 表示不限制
+    #[serde(default = "default_adaptive_interval")]
+    pub adaptive_interval: bool,  // 根据画面变化和模型耗时自动调整截屏间隔
+    #[serde(default = "default_min_interval_ms")]
+    pub min_interval_ms: u64,  // 自适应间隔下限（毫秒）
+    #[serde(default = "default_max_interval_ms")]
+    pub max_interval_ms: u64,  // 自适应间隔上限（毫秒）
+    #[serde(default = "default_change_detection")]
+    pub change_detection: String,  // 变化检测模式: simple（整帧哈希）/ tiles（分块哈希）
+    #[serde(default = "default_tile_columns")]
+    pub tile_columns: u32,  // 分块模式的列数
+    #[serde(default = "default_tile_rows")]
+    pub tile_rows: u32,  // 分块模式的行数
+    #[serde(default = "default_tile_bit_threshold")]
+    pub tile_bit_threshold: u32,  // 单个分块哈希差异超过多少位（0-64）视为该块变化
+    #[serde(default)]
+    pub tile_change_ratio: f32,  // 加权变化比例阈值，0 表示任一分块变化即视为画面变化
+    #[serde(default)]
+    pub privacy_apps: Vec<String>,  // 隐私应用名单（包含匹配，不区分大小写）
+    #[serde(default)]
+    pub privacy_patterns: Vec<String>,  // 隐私标题/关键词正则，匹配应用名、摘要和 detail
+    #[serde(default = "default_privacy_cooldown_seconds")]
+    pub privacy_cooldown_seconds: u64,  // 命中隐私规则后暂停上传的时间（秒）
+    #[serde(default)]
+    pub redaction_regions: Vec<RedactionRegion>,  // 上传和保存前始终遮挡的屏幕区域
+    #[serde(default = "default_scrub_secrets")]
+    pub scrub_secrets: bool,  // 摘要和请求日志落盘前替换密钥、邮箱、手机号等敏感信息
+    #[serde(default)]
+    pub scrub_patterns: Vec<String>,  // 自定义脱敏正则，命中内容替换为 [REDACTED]
+    #[serde(default)]
+    pub schedule: Vec<CaptureWindow>,  // 每周监控时段，为空表示全天监控
+    #[serde(default = "default_analysis_concurrency")]
+    pub analysis_concurrency: usize,  // 同时进行的模型分析数
+    #[serde(default = "default_analysis_queue_size")]
+    pub analysis_queue_size: usize,  // 等待分析的帧队列长度，满时丢弃最旧的帧
+    #[serde(default = "default_analysis_batch_size")]
+    pub analysis_batch_size: usize,  // 一次请求最多分析的帧数，1 表示逐帧分析
+    #[serde(default = "default_ocr_engine")]
+    pub ocr_engine: String,  // 本地文字识别引擎：none / tesseract（需启用 ocr-tesseract 构建）
+    #[serde(default = "default_ocr_languages")]
+    pub ocr_languages: String,  // 识别语言，如 chi_sim+eng
+    #[serde(default)]
+    pub ocr_data_path: String,  // 语言数据目录，为空时使用引擎默认位置
+    #[serde(default = "default_ocr_max_chars")]
+    pub ocr_max_chars: usize,  // 每帧保存的识别文字最大字符数
+}
+
+/// 监控时段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureWindow {
+    /// 生效的星期（1 = 周一 ... 7 = 周日），为空表示每天
+    #[serde(default)]
+    pub days: Vec<u32>,
+    /// 开始时间，格式 HH:MM
+    pub start: String,
+    /// 结束时间，格式 HH:MM；早于开始时间表示跨午夜
+    pub end: String,
+}
+
+/// 遮挡区域
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRegion {
+    /// 生效的显示器 id（拼接模式为 "stitched"），为空表示所有显示器
+    #[serde(default)]
+    pub display_id: Option<String>,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// 坐标单位："px" 为像素，"ratio" 为相对画面宽高的比例 (0.0-1.0)
+    #[serde(default = "default_redaction_unit")]
+    pub unit: String,
+    /// 遮挡方式："black" 涂黑，"blur" 模糊
+    #[serde(default = "default_redaction_style")]
+    pub style: String,
 }
 
 fn default_skip_unchanged() -> bool {
@@ -78,6 +186,87 @@ fn default_alert_cooldown_seconds() -> u64 {
     120
 }
 
+fn default_capture_mode() -> String {
+    "primary".to_string()
+}
+
+fn default_upload_max_width() -> u32 {
+    1920
+}
+
+fn default_upload_max_height() -> u32 {
+    1080
+}
+
+fn default_adaptive_interval() -> bool {
+    true
+}
+
+fn default_min_interval_ms() -> u64 {
+    1000
+}
+
+fn default_max_interval_ms() -> u64 {
+    15000
+}
+
+/// 旧配置没有该字段时沿用整帧哈希，原来调好的 `change_threshold` 继续生效；新安装在 `Config::default()` 中使用分块检测
+fn default_change_detection() -> String {
+    "simple".to_string()
+}
+
+fn default_tile_columns() -> u32 {
+    16
+}
+
+fn default_tile_rows() -> u32 {
+    9
+}
+
+fn default_tile_bit_threshold() -> u32 {
+    4
+}
+
+fn default_privacy_cooldown_seconds() -> u64 {
+    300
+}
+
+fn default_analysis_concurrency() -> usize {
+    1
+}
+
+fn default_analysis_queue_size() -> usize {
+    2
+}
+
+fn default_analysis_batch_size() -> usize {
+    1
+}
+
+fn default_ocr_engine() -> String {
+    "none".to_string()
+}
+
+fn default_ocr_languages() -> String {
+    "chi_sim+eng".to_string()
+}
+
+fn default_ocr_max_chars() -> usize {
+    4000
+}
+
+fn default_scrub_secrets() -> bool {
+    true
+}
+
+fn default_redaction_unit() -> String {
+    "px".to_string()
+}
+
+fn default_redaction_style() -> String {
+    "black".to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub retention_days: u32,
@@ -86,12 +275,48 @@ pub struct StorageConfig {
     pub max_context_chars: usize,  // 上下文最大字符数，用户可调整
     #[serde(default)]
     pub auto_clear_on_start: bool,  // 启动时自动清空历史
+    #[serde(default = "default_merge_spans")]
+    pub merge_spans: bool,  // 把连续且几乎相同的记录合并为一段
+    #[serde(default = "default_span_similarity")]
+    pub span_similarity: f64,  // 摘要相似度达到该值时合并（0-1）
+    #[serde(default = "default_span_max_gap_seconds")]
+    pub span_max_gap_seconds: u64,  // 与上一条记录间隔超过该值时不合并
+    #[serde(default = "default_session_idle_seconds")]
+    pub session_idle_seconds: u64,  // 工作段切分和时间统计：记录间隔超过该值视为离开
+    #[serde(default = "default_session_topic_similarity")]
+    pub session_topic_similarity: f64,  // 工作段切分：摘要相似度低于该值视为换了任务（0-1）
+    #[serde(default = "default_session_switch_seconds")]
+    pub session_switch_seconds: u64,  // 工作段切分：切换持续超过该值才另起一段
 }
 
 fn default_max_context_chars() -> usize {
     10000  // 默认10000字符
 }
 
+fn default_merge_spans() -> bool {
+    true
+}
+
+fn default_span_similarity() -> f64 {
+    0.6
+}
+
+fn default_span_max_gap_seconds() -> u64 {
+    180
+}
+
+fn default_session_idle_seconds() -> u64 {
+    900
+}
+
+fn default_session_topic_similarity() -> f64 {
+    0.1
+}
+
+fn default_session_switch_seconds() -> u64 {
+    120
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -118,12 +343,47 @@ impl Default for Config {
                 recent_detail_limit: 3,
                 alert_confidence_threshold: 0.7,
                 alert_cooldown_seconds: 120,
+                capture_mode: default_capture_mode(),
+                display_id: None,
+                upload_max_width: default_upload_max_width(),
+                upload_max_height: default_upload_max_height(),
+                save_quality: None,
+                save_max_width: 0,
+                save_max_height: 0,
+                adaptive_interval: true,
+                min_interval_ms: 1000,
+                max_interval_ms: 15000,
+                change_detection: "tiles".to_string(),
+                tile_columns: default_tile_columns(),
+                tile_rows: default_tile_rows(),
+                tile_bit_threshold: default_tile_bit_threshold(),
+                tile_change_ratio: 0.0,
+                privacy_apps: Vec::new(),
+                privacy_patterns: Vec::new(),
+                privacy_cooldown_seconds: default_privacy_cooldown_seconds(),
+                redaction_regions: Vec::new(),
+                scrub_secrets: default_scrub_secrets(),
+                scrub_patterns: Vec::new(),
+                schedule: Vec::new(),
+                analysis_concurrency: default_analysis_concurrency(),
+                analysis_queue_size: default_analysis_queue_size(),
+                analysis_batch_size: default_analysis_batch_size(),
+                ocr_engine: default_ocr_engine(),
+                ocr_languages: default_ocr_languages(),
+                ocr_data_path: String::new(),
+                ocr_max_chars: default_ocr_max_chars(),
             },
             storage: StorageConfig {
                 retention_days: 7,
                 max_screenshots: 10000,
                 max_context_chars: 10000,  // 默认10000字符
                 auto_clear_on_start: false,
+                merge_spans: true,
+                span_similarity: 0.6,
+                span_max_gap_seconds: 180,
+                session_idle_seconds: default_session_idle_seconds(),
+                session_topic_similarity: default_session_topic_similarity(),
+                session_switch_seconds: default_session_switch_seconds(),
             },
         }
     }
@@ -132,7 +392,7 @@ impl Default for Config {
 // ============ 分层记录结构 ============
 
 /// 原始记录（每秒级别）
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SummaryRecord {
     pub timestamp: String,
     pub summary: String,
@@ -153,6 +413,182 @@ pub struct SummaryRecord {
     pub detail: String,
     #[serde(default)]
     pub detail_ref: String,
+    #[serde(default)]
+    pub display_id: String,  // 来源显示器 id，拼接模式为 stitched
+    #[serde(default)]
+    pub change_region: Option<ChangeRegion>,  // 相对上一帧变化分块的外接矩形
+    #[serde(default)]
+    pub app_name: String,  // 归一化后的应用名，如 Visual Studio Code
+    #[serde(default)]
+    pub file: String,  // 画面中正在处理的文件名，未识别时为空
+    #[serde(default)]
+    pub activity_type: String,  // 活动类型：coding / browsing / terminal 等
+    #[serde(default)]
+    pub project: String,  // 自定义分类规则给出的项目名，未命中时为空
+    #[serde(default)]
+    pub start: String,  // 合并为一段时的开始时间，timestamp 为结束时间；单帧记录为空
+    #[serde(default = "default_frame_count")]
+    pub frame_count: u32,  // 合并的帧数
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub screen_text: String,  // 本地文字识别得到的屏幕原文，未开启识别时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<IssueInfo>,  // 从报错原文解析出的来源、错误码、文件和行号，未识别时为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub last_seen: String,  // 之后画面一直没有变化（跳过分析）时，最后一次看到该画面的时间
+}
+
+fn default_frame_count() -> u32 {
+    1
+}
+
+impl SummaryRecord {
+    /// 归一化应用名；旧记录没有该字段时退回原始应用名
+    pub fn app_label(&self) -> &str {
+        if self.app_name.is_empty() {
+            &self.app
+        } else {
+            &self.app_name
+        }
+    }
+
+    /// 最后一次看到该画面的时间：画面没有变化而跳过的帧会延后该时间
+    pub fn end_time(&self) -> &str {
+        if self.last_seen.as_str() > self.timestamp.as_str() {
+            &self.last_seen
+        } else {
+            &self.timestamp
+        }
+    }
+
+    /// 开始时间；单帧记录即 timestamp
+    pub fn start_time(&self) -> &str {
+        if self.start.is_empty() {
+            &self.timestamp
+        } else {
+            &self.start
+        }
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frame_count.max(1)
+    }
+
+    /// 上下文中显示的时间：单帧为 HH:MM:SS，合并的一段为 HH:MM–HH:MM
+    pub fn time_label(&self) -> String {
+        let end = self.timestamp.get(11..19).unwrap_or(&self.timestamp);
+        if self.frames() <= 1 || self.start.is_empty() {
+            return end.to_string();
+        }
+        let start = self.start.get(11..16).unwrap_or(&self.start);
+        format!("{}–{}", start, end.get(..5).unwrap_or(end))
+    }
+}
+
+/// 连续记录合并规则
+#[derive(Debug, Clone)]
+pub struct SpanPolicy {
+    pub similarity: f64,
+    pub max_gap_seconds: i64,
+}
+
+impl SpanPolicy {
+    /// 未开启合并时返回 None
+    pub fn from_config(config: &StorageConfig) -> Option<Self> {
+        if !config.merge_spans {
+            return None;
+        }
+        Some(Self {
+            similarity: config.span_similarity.clamp(0.0, 1.0),
+            max_gap_seconds: config.span_max_gap_seconds as i64,
+        })
+    }
+
+    /// 找到新记录可以并入的一段：同一显示器上的上一条记录
+    ///
+    /// 多显示器逐个分析时各显示器的记录交错写入，向前跳过其它显示器的记录；
+    /// 遇到暂停等状态记录或超出最大间隔时停止查找
+    fn span_index(&self, records: &[SummaryRecord], record: &SummaryRecord) -> Option<usize> {
+        let now = parse_timestamp(&record.timestamp)?;
+        for (index, previous) in records.iter().enumerate().rev() {
+            if previous.app.is_empty() {
+                return None;
+            }
+            if previous.display_id == record.display_id {
+                return Some(index).filter(|_| self.can_extend(previous, record));
+            }
+            match parse_timestamp(previous.end_time()) {
+                Some(at) if (now - at).num_seconds() <= self.max_gap_seconds => {}
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// 同一应用、同一显示器上摘要几乎相同的正常记录才能并入上一段
+    fn can_extend(&self, last: &SummaryRecord, record: &SummaryRecord) -> bool {
+        if last.action != "active" || record.action != "active" || last.has_issue || record.has_issue {
+            return false;
+        }
+        if last.app_label() != record.app_label()
+            || last.display_id != record.display_id
+            || last.file != record.file
+            || last.activity_type != record.activity_type
+            || last.project != record.project
+        {
+            return false;
+        }
+
+        let gap = match (parse_timestamp(last.end_time()), parse_timestamp(&record.timestamp)) {
+            (Some(prev), Some(next)) => (next - prev).num_seconds(),
+            _ => return false,
+        };
+        if gap < 0 || gap > self.max_gap_seconds {
+            return false;
+        }
+
+        DiffAnalyzer::text_similarity(&last.summary, &record.summary) >= self.similarity
+    }
+}
+
+/// 把新记录并入上一段：保留开始时间和摘要，画面细节、截图改用最新一帧
+///
+/// 被替换的截图可能仍被排队中的帧复用，不在这里删除，由孤立截图清理处理
+fn extend_span(last: &mut SummaryRecord, record: &SummaryRecord) {
+    if last.start.is_empty() {
+        last.start = last.timestamp.clone();
+    }
+    last.timestamp = record.timestamp.clone();
+    last.last_seen = String::new();
+    last.frame_count = last.frames() + record.frames();
+    last.confidence = record.confidence;
+    last.change_region = record.change_region.clone();
+    for keyword in &record.keywords {
+        if !last.keywords.contains(keyword) {
+            last.keywords.push(keyword.clone());
+        }
+    }
+    if !record.detail.is_empty() {
+        last.detail = record.detail.clone();
+    }
+    if !record.screen_text.is_empty() {
+        last.screen_text = record.screen_text.clone();
+    }
+    if !record.detail_ref.is_empty() {
+        last.detail_ref = record.detail_ref.clone();
+    }
+}
+
+pub(crate) fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+/// 画面变化区域（原图像素坐标）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
 /// 聚合记录（5分钟级别）
@@ -167,6 +603,10 @@ pub struct AggregatedRecord {
     pub record_count: u32,         // 原始记录数量
     pub has_errors: bool,          // 是否有错误
     pub error_summary: Option<String>, // 错误概要
+    #[serde(default)]
+    pub activity_types: Vec<String>, // 活动类型（按出现次数排序）
+    #[serde(default)]
+    pub files: Vec<String>,        // 涉及的文件
 }
 
 /// 日摘要
@@ -180,6 +620,14 @@ pub struct DailySummary {
     pub day_summary: Option<String>, // 当天总结
 }
 
+/// 一天的工作段，单独保存在 sessions/ 下，避免和写入记录的流程争用同一个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailySessions {
+    pub date: String,
+    #[serde(default)]
+    pub sessions: Vec<WorkSession>,
+}
+
 // ============ 存储管理器 ============
 
 pub struct StorageManager {
@@ -201,6 +649,7 @@ impl StorageManager {
             self.data_dir.clone(),
             self.data_dir.join("summaries"),
             self.data_dir.join("aggregated"),
+            self.data_dir.join("sessions"),
             self.data_dir.join("profiles"),
             self.data_dir.join("screenshots"),
             self.data_dir.join("logs"),
@@ -219,11 +668,198 @@ impl StorageManager {
         Ok(self.data_dir.join("screenshots"))
     }
 
+    /// 按内容指纹保存截图，返回记录中引用的文件名
+    ///
+    /// 指纹相同且文件仍存在时直接复用已有文件，否则调用 `write` 写入 `filename`
+    ///
+    /// 编码和生成缩略图都不持有索引锁，多个分析任务可以同时保存
+    pub fn store_screenshot<F>(&self, content_key: &str, filename: &str, write: F) -> Result<String, String>
+    where
+        F: FnOnce(&Path) -> Result<(), String>,
+    {
+        let dir = self.screenshots_dir()?;
+        if let Some(existing) = with_screenshot_index(&dir, |index| index.lookup(content_key)) {
+            return Ok(existing);
+        }
+
+        let path = dir.join(filename);
+        // 回放已保存的截图时文件已存在，直接复用
+        let written = !path.exists();
+        if written {
+            // 先写临时文件再改名，不会留下写了一半的截图
+            let temp = dir.join(format!("{}.tmp", filename));
+            if let Err(err) = write(&temp).and_then(|_| {
+                fs::rename(&temp, &path).map_err(|e| format!("保存截图失败: {}", e))
+            }) {
+                let _ = fs::remove_file(&temp);
+                return Err(err);
+            }
+        }
+
+        // 编码期间另一个任务可能已经保存了相同内容，以先写入索引的为准
+        let stored = with_screenshot_index(&dir, |index| match index.lookup(content_key) {
+            Some(existing) => Ok(existing),
+            None => index.insert(content_key, filename).map(|_| filename.to_string()),
+        })?;
+        if stored != filename {
+            if written {
+                let _ = fs::remove_file(&path);
+            }
+            return Ok(stored);
+        }
+
+        // 缩略图生成失败不影响截图本身，读取时会再尝试生成
+        if let Err(err) = ensure_thumbnail(&dir, filename) {
+            eprintln!("生成缩略图失败: {}", err);
+        }
+        Ok(stored)
+    }
+
+    /// 删除截图目录中没有被任何记录引用的文件，返回 (删除数量, 释放字节数)
+    pub fn cleanup_orphan_screenshots(&self) -> Result<(usize, u64), String> {
+        let dir = self.screenshots_dir()?;
+        let referenced = self.collect_screenshot_refs(None)?;
+        let grace = std::time::Duration::from_secs(ORPHAN_GRACE_SECONDS);
+        let now = SystemTime::now();
+
+        with_screenshot_index(&dir, |index| {
+            let entries = fs::read_dir(&dir)
+                .map_err(|e| format!("读取截图目录失败: {}", e))?;
+
+            let mut removed = 0usize;
+            let mut freed = 0u64;
+            for entry in entries {
+                let entry = entry.map_err(|e| format!("读取截图目录失败: {}", e))?;
+                let path = entry.path();
+                if !is_screenshot_file(&path) {
+                    continue;
+                }
+                let name = match path.file_name().and_then(|s| s.to_str()) {
+                    Some(name) => name.to_string(),
+                    None => continue,
+                };
+                if referenced.contains(&name) {
+                    continue;
+                }
+
+                let metadata = match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(_) => continue,
+                };
+                let recent = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| now.duration_since(time).ok())
+                    .map(|age| age < grace)
+                    .unwrap_or(true);
+                if recent {
+                    continue;
+                }
+
+                if fs::remove_file(&path).is_ok() {
+                    let _ = fs::remove_file(thumbnail_path(&dir, &name));
+                    removed += 1;
+                    freed += metadata.len();
+                }
+            }
+
+            index.compact()?;
+            Ok((removed, freed))
+        })
+    }
+
+    /// 读取记录引用的截图原图
+    pub fn read_screenshot(&self, detail_ref: &str) -> Result<Vec<u8>, String> {
+        let path = self.screenshot_path(detail_ref)?;
+        fs::read(&path).map_err(|e| format!("读取截图失败: {}", e))
+    }
+
+    /// 读取截图的缩略图（JPEG）；旧截图没有缩略图时现场生成
+    pub fn read_thumbnail(&self, detail_ref: &str) -> Result<Vec<u8>, String> {
+        let path = self.screenshot_path(detail_ref)?;
+        let dir = self.screenshots_dir()?;
+        let name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| "截图引用无效".to_string())?;
+        let thumbnail = ensure_thumbnail(&dir, name)?;
+        fs::read(&thumbnail).map_err(|e| format!("读取缩略图失败: {}", e))
+    }
+
+    /// 把记录中的 detail_ref 解析为截图目录内的文件路径，拒绝目录穿越和目录外的链接
+    pub fn screenshot_path(&self, detail_ref: &str) -> Result<PathBuf, String> {
+        let name = detail_ref.trim();
+        let is_plain_name = !name.is_empty()
+            && !name.contains(['/', '\\', ':'])
+            && Path::new(name).file_name().and_then(|s| s.to_str()) == Some(name);
+        if !is_plain_name || !is_screenshot_file(Path::new(name)) {
+            return Err("截图引用无效".to_string());
+        }
+
+        let dir = self
+            .screenshots_dir()?
+            .canonicalize()
+            .map_err(|e| format!("读取截图目录失败: {}", e))?;
+        let path = dir
+            .join(name)
+            .canonicalize()
+            .map_err(|_| format!("截图不存在: {}", name))?;
+        if !path.starts_with(&dir) || !path.is_file() {
+            return Err("截图引用无效".to_string());
+        }
+        Ok(path)
+    }
+
+    /// 收集所有记录引用的截图文件名，可排除某一天
+    fn collect_screenshot_refs(&self, exclude_date: Option<&str>) -> Result<HashSet<String>, String> {
+        let summaries_dir = self.data_dir.join("summaries");
+        let mut refs = HashSet::new();
+        if !summaries_dir.exists() {
+            return Ok(refs);
+        }
+
+        let entries = fs::read_dir(&summaries_dir)
+            .map_err(|e| format!("读取摘要目录失败: {}", e))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("读取摘要目录失败: {}", e))?;
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            let date = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            if exclude_date == Some(date) {
+                continue;
+            }
+
+            let daily = match fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<DailySummary>(&content).ok())
+            {
+                Some(daily) => daily,
+                None => continue,
+            };
+            for record in daily.records {
+                if !record.detail_ref.is_empty() {
+                    refs.insert(record.detail_ref);
+                }
+            }
+        }
+
+        Ok(refs)
+    }
+
     pub fn logs_dir(&self) -> Result<PathBuf, String> {
         self.ensure_dirs()?;
         Ok(self.data_dir.join("logs"))
     }
 
+    /// 延时回放等导出文件的默认目录
+    pub fn exports_dir(&self) -> Result<PathBuf, String> {
+        let dir = self.data_dir.join("exports");
+        fs::create_dir_all(&dir).map_err(|e| format!("创建目录失败 {:?}: {}", dir, e))?;
+        Ok(dir)
+    }
+
     pub fn write_log_snapshot(&self, prefix: &str, content: &str) -> Result<PathBuf, String> {
         let dir = self.logs_dir()?;
         let now = Local::now();
@@ -263,6 +899,24 @@ impl StorageManager {
             .map_err(|e| format!("保存配置失败: {}", e))
     }
 
+    /// config.json 的修改时间，用于发现外部编辑；文件不存在时返回 None
+    pub fn config_modified_time(&self) -> Option<SystemTime> {
+        fs::metadata(self.data_dir.join("config.json"))
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// 自定义活动分类规则文件，与 config.json 放在同一目录
+    pub fn activity_rules_path(&self) -> PathBuf {
+        self.data_dir.join("activity_rules.json")
+    }
+
+    pub fn activity_rules_modified_time(&self) -> Option<SystemTime> {
+        fs::metadata(self.activity_rules_path())
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
     // ============ 配置方案管理 ============
 
     pub fn list_profiles(&self) -> Result<Vec<String>, String> {
@@ -377,7 +1031,13 @@ impl StorageManager {
     }
 
     pub fn save_summary(&self, record: &SummaryRecord) -> Result<(), String> {
+        self.save_summary_merged(record, None)
+    }
+
+    /// 保存一条记录；给出合并规则时，与上一条几乎相同的记录并入上一段而不是新增
+    pub fn save_summary_merged(&self, record: &SummaryRecord, merge: Option<&SpanPolicy>) -> Result<(), String> {
         self.ensure_dirs()?;
+        let _guard = SUMMARY_FILE_LOCK.lock();
 
         let date = &record.timestamp[..10];
         let summary_path = self.data_dir.join("summaries").join(format!("{}.json", date));
@@ -400,11 +1060,18 @@ impl StorageManager {
             }
         };
 
-        daily.records.push(record.clone());
+        let span = merge
+            .and_then(|policy| policy.span_index(&daily.records, record))
+            .map(|index| &mut daily.records[index]);
+        if let Some(last) = span {
+            extend_span(last, record);
+        } else {
+            daily.records.push(record.clone());
 
-        // 检查是否需要聚合（每300条触发一次，约5分钟）
-        if daily.records.len() % 300 == 0 {
-            self.trigger_aggregation(&mut daily)?;
+            // 检查是否需要聚合（每300条触发一次，约5分钟）
+            if daily.records.len() % 300 == 0 {
+                self.trigger_aggregation(&mut daily)?;
+            }
         }
 
         let content = serde_json::to_string_pretty(&daily)
@@ -414,8 +1081,42 @@ impl StorageManager {
             .map_err(|e| format!("保存摘要失败: {}", e))
     }
 
+    /// 画面没有变化时，把该显示器最近一条记录的 `last_seen` 更新为 `at`
+    ///
+    /// 暂停等状态记录之后的记录、隐私占位记录不再更新，没有可更新的记录时忽略
+    pub fn mark_seen(&self, display_id: &str, at: &str) -> Result<(), String> {
+        let date = at.get(..10).ok_or_else(|| format!("时间格式无效: {}", at))?;
+        let summary_path = self.data_dir.join("summaries").join(format!("{}.json", date));
+        let _guard = SUMMARY_FILE_LOCK.lock();
+        if !summary_path.exists() {
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(&summary_path)
+            .map_err(|e| format!("读取摘要失败: {}", e))?;
+        let mut daily: DailySummary = serde_json::from_str(&content)
+            .map_err(|e| format!("解析摘要失败: {}", e))?;
+        let target = daily
+            .records
+            .iter_mut()
+            .rev()
+            .take_while(|r| !r.app.is_empty())
+            .find(|r| r.display_id == display_id)
+            .filter(|r| r.action != "private");
+        match target {
+            Some(record) if at > record.end_time() => record.last_seen = at.to_string(),
+            _ => return Ok(()),
+        }
+
+        let content = serde_json::to_string_pretty(&daily)
+            .map_err(|e| format!("序列化摘要失败: {}", e))?;
+        fs::write(&summary_path, content)
+            .map_err(|e| format!("保存摘要失败: {}", e))
+    }
+
     pub fn delete_summaries_for_date(&self, date: &str) -> Result<usize, String> {
         self.ensure_dirs()?;
+        let _guard = SUMMARY_FILE_LOCK.lock();
         let summary_path = self.data_dir.join("summaries").join(format!("{}.json", date));
         if !summary_path.exists() {
             return Ok(0);
@@ -426,25 +1127,28 @@ impl StorageManager {
         let daily: DailySummary = serde_json::from_str(&content)
             .map_err(|e| format!("解析摘要失败: {}", e))?;
 
+        // 截图可能被其它日期的记录共用，只删除不再被引用的文件
+        let still_referenced = self.collect_screenshot_refs(Some(date))?;
         let mut removed = 0usize;
         for record in daily.records {
             removed += 1;
-            if !record.detail_ref.is_empty() {
+            if !record.detail_ref.is_empty() && !still_referenced.contains(&record.detail_ref) {
                 if let Ok(dir) = self.screenshots_dir() {
-                    let path = dir.join(&record.detail_ref);
-                    let _ = fs::remove_file(&path);
+                    remove_screenshot_files(&dir, &record.detail_ref);
                 }
             }
         }
 
         fs::remove_file(&summary_path)
             .map_err(|e| format!("删除摘要失败: {}", e))?;
+        let _ = fs::remove_file(self.sessions_path(date));
 
         Ok(removed)
     }
 
     pub fn delete_all_summaries(&self) -> Result<usize, String> {
         self.ensure_dirs()?;
+        let _guard = SUMMARY_FILE_LOCK.lock();
         let summaries_dir = self.data_dir.join("summaries");
         if !summaries_dir.exists() {
             return Ok(0);
@@ -474,8 +1178,7 @@ impl StorageManager {
                     total_removed += 1;
                     if !record.detail_ref.is_empty() {
                         if let Ok(dir) = self.screenshots_dir() {
-                            let shot = dir.join(&record.detail_ref);
-                            let _ = fs::remove_file(&shot);
+                            remove_screenshot_files(&dir, &record.detail_ref);
                         }
                     }
                 }
@@ -484,9 +1187,46 @@ impl StorageManager {
             let _ = fs::remove_file(&path);
         }
 
+        if let Ok(dir) = self.screenshots_dir() {
+            with_screenshot_index(&dir, ScreenshotIndex::clear);
+        }
+        let _ = fs::remove_dir_all(self.data_dir.join("sessions"));
+
         Ok(total_removed)
     }
 
+    // ============ 工作段 ============
+
+    fn sessions_path(&self, date: &str) -> PathBuf {
+        self.data_dir.join("sessions").join(format!("{}.json", date))
+    }
+
+    /// 读取已保存的工作段；还没有切分过时返回空列表
+    pub fn get_sessions(&self, date: &str) -> Result<Vec<WorkSession>, String> {
+        let path = self.sessions_path(date);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("读取工作段失败: {}", e))?;
+        let daily: DailySessions = serde_json::from_str(&content)
+            .map_err(|e| format!("解析工作段失败: {}", e))?;
+        Ok(daily.sessions)
+    }
+
+    pub fn save_sessions(&self, date: &str, sessions: &[WorkSession]) -> Result<(), String> {
+        self.ensure_dirs()?;
+        let daily = DailySessions {
+            date: date.to_string(),
+            sessions: sessions.to_vec(),
+        };
+        let content = serde_json::to_string_pretty(&daily)
+            .map_err(|e| format!("序列化工作段失败: {}", e))?;
+        fs::write(self.sessions_path(date), content)
+            .map_err(|e| format!("保存工作段失败: {}", e))
+    }
+
     // ============ 聚合管理 ============
 
     fn trigger_aggregation(&self, daily: &mut DailySummary) -> Result<(), String> {
@@ -508,18 +1248,28 @@ impl StorageManager {
     }
 
     fn aggregate_records(&self, records: &[SummaryRecord]) -> AggregatedRecord {
-        let start_time = records.last().map(|r| r.timestamp.clone()).unwrap_or_default();
+        let start_time = records.last().map(|r| r.start_time().to_string()).unwrap_or_default();
         let end_time = records.first().map(|r| r.timestamp.clone()).unwrap_or_default();
 
         // 统计应用使用
         let mut app_counts: HashMap<String, u32> = HashMap::new();
+        let mut activity_counts: HashMap<String, u32> = HashMap::new();
+        let mut file_counts: HashMap<String, u32> = HashMap::new();
         let mut all_keywords: HashMap<String, u32> = HashMap::new();
         let mut activities: Vec<String> = Vec::new();
         let mut has_errors = false;
         let mut error_messages: Vec<String> = Vec::new();
 
         for record in records {
-            *app_counts.entry(record.app.clone()).or_insert(0) += 1;
+            // 合并的一段按帧数计
+            let frames = record.frames();
+            *app_counts.entry(record.app_label().to_string()).or_insert(0) += frames;
+            if !record.activity_type.is_empty() {
+                *activity_counts.entry(record.activity_type.clone()).or_insert(0) += frames;
+            }
+            if !record.file.is_empty() {
+                *file_counts.entry(record.file.clone()).or_insert(0) += frames;
+            }
 
             for kw in &record.keywords {
                 *all_keywords.entry(kw.clone()).or_insert(0) += 1;
@@ -527,7 +1277,10 @@ impl StorageManager {
 
             if record.action == "error" || record.action == "issue" {
                 has_errors = true;
-                error_messages.push(record.summary.clone());
+                error_messages.push(match &record.issue {
+                    Some(issue) => issue.describe(),
+                    None => record.summary.clone(),
+                });
             }
 
             // 提取主要活动（去重）
@@ -541,6 +1294,14 @@ impl StorageManager {
         apps.sort_by(|a, b| b.1.cmp(&a.1));
         let top_apps: Vec<String> = apps.into_iter().take(3).map(|(k, _)| k).collect();
 
+        let mut activity_types: Vec<_> = activity_counts.into_iter().collect();
+        activity_types.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let activity_types: Vec<String> = activity_types.into_iter().take(3).map(|(k, _)| k).collect();
+
+        let mut files: Vec<_> = file_counts.into_iter().collect();
+        files.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let files: Vec<String> = files.into_iter().take(5).map(|(k, _)| k).collect();
+
         // 排序获取最常见的关键词
         let mut keywords: Vec<_> = all_keywords.into_iter().collect();
         keywords.sort_by(|a, b| b.1.cmp(&a.1));
@@ -560,18 +1321,61 @@ impl StorageManager {
             apps: top_apps,
             main_activities: activities,
             keywords: top_keywords,
-            record_count: records.len() as u32,
+            record_count: records.iter().map(|r| r.frames()).sum(),
             has_errors,
             error_summary: if has_errors {
                 Some(error_messages.join("; "))
             } else {
                 None
             },
+            activity_types,
+            files,
         }
     }
 
     // ============ 智能检索 ============
 
+    /// 在识别出的屏幕原文中精确查找（不区分大小写），从最新的记录开始，最多返回 `limit` 条
+    pub fn search_screen_text(&self, text: &str, days: u32, limit: usize) -> Vec<ScreenTextMatch> {
+        let needle = text.trim().to_lowercase();
+        if needle.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let mut matches = Vec::new();
+        for offset in 0..days.max(1) {
+            let date = (Local::now() - Duration::days(offset as i64))
+                .format("%Y-%m-%d")
+                .to_string();
+            let records = match self.get_summaries(&date) {
+                Ok(records) => records,
+                Err(_) => continue,
+            };
+
+            for record in records.into_iter().rev() {
+                let line = match record
+                    .screen_text
+                    .lines()
+                    .find(|line| line.to_lowercase().contains(&needle))
+                {
+                    Some(line) => line.trim().to_string(),
+                    None => continue,
+                };
+                matches.push(ScreenTextMatch {
+                    timestamp: record.timestamp.clone(),
+                    app: record.app_label().to_string(),
+                    summary: record.summary,
+                    detail_ref: record.detail_ref,
+                    line,
+                });
+                if matches.len() >= limit {
+                    return matches;
+                }
+            }
+        }
+        matches
+    }
+
     /// 根据时间范围和关键词智能检索记录
     pub fn smart_search(&self, query: &SearchQuery) -> Result<SearchResult, String> {
         let today = Local::now().format("%Y-%m-%d").to_string();
@@ -585,12 +1389,13 @@ impl StorageManager {
 
                 let filtered: Vec<_> = records.into_iter()
                     .filter(|r| r.timestamp >= cutoff_str)
-                    .filter(|r| query.matches_keywords(r))
+                    .filter(|r| query.matches_keywords(r) && query.matches_filters(r))
                     .collect();
 
                 Ok(SearchResult {
                     records: filtered,
                     aggregated: Vec::new(),
+                    sessions: Vec::new(),
                     source: "原始记录".to_string(),
                 })
             }
@@ -598,14 +1403,15 @@ impl StorageManager {
                 // 今天：优先使用聚合记录
                 let daily = self.load_daily(&today)?;
 
-                if !query.keywords.is_empty() {
-                    // 有关键词：搜索原始记录
+                if !query.keywords.is_empty() || query.has_filters() {
+                    // 有关键词或过滤条件：搜索原始记录
                     let filtered: Vec<_> = daily.records.into_iter()
-                        .filter(|r| query.matches_keywords(r))
+                        .filter(|r| query.matches_keywords(r) && query.matches_filters(r))
                         .collect();
                     Ok(SearchResult {
                         records: filtered,
                         aggregated: Vec::new(),
+                        sessions: Vec::new(),
                         source: "关键词搜索".to_string(),
                     })
                 } else {
@@ -614,6 +1420,7 @@ impl StorageManager {
                     Ok(SearchResult {
                         records: recent,
                         aggregated: daily.aggregated,
+                        sessions: Vec::new(),
                         source: "聚合记录".to_string(),
                     })
                 }
@@ -626,13 +1433,16 @@ impl StorageManager {
                     let date = (Local::now() - Duration::days(i as i64))
                         .format("%Y-%m-%d").to_string();
                     if let Ok(daily) = self.load_daily(&date) {
-                        all_aggregated.extend(daily.aggregated);
+                        all_aggregated.extend(
+                            daily.aggregated.into_iter().filter(|agg| query.matches_aggregated(agg)),
+                        );
                     }
                 }
 
                 Ok(SearchResult {
                     records: Vec::new(),
                     aggregated: all_aggregated,
+                    sessions: Vec::new(),
                     source: "历史聚合".to_string(),
                 })
             }
@@ -694,6 +1504,164 @@ fn sanitize_profile_name(name: &str) -> Result<String, String> {
     Ok(base.to_string())
 }
 
+/// 截图内容索引（内容指纹 -> 文件名）
+///
+/// 内存中保存全部条目，磁盘上只追加新条目；加载时和失效行过多时去掉文件已不存在的条目并重写
+struct ScreenshotIndex {
+    dir: PathBuf,
+    entries: HashMap<String, String>,
+    /// 索引文件中的行数，包括已失效的条目
+    lines: usize,
+}
+
+impl ScreenshotIndex {
+    fn load(dir: &Path) -> Self {
+        let mut entries: HashMap<String, String> = fs::read_to_string(dir.join(LEGACY_SCREENSHOT_INDEX_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        if let Ok(content) = fs::read_to_string(dir.join(SCREENSHOT_INDEX_FILE)) {
+            for line in content.lines() {
+                if let Ok((key, name)) = serde_json::from_str::<(String, String)>(line) {
+                    entries.insert(key, name);
+                }
+            }
+        }
+
+        let mut index = Self {
+            dir: dir.to_path_buf(),
+            entries,
+            lines: usize::MAX,
+        };
+        match index.compact() {
+            Ok(()) => {
+                let _ = fs::remove_file(dir.join(LEGACY_SCREENSHOT_INDEX_FILE));
+            }
+            Err(err) => eprintln!("{}", err),
+        }
+        index
+    }
+
+    /// 查找指纹对应的截图；文件已被删除时移除该条目
+    fn lookup(&mut self, key: &str) -> Option<String> {
+        let name = self.entries.get(key)?;
+        if self.dir.join(name).exists() {
+            return Some(name.clone());
+        }
+        self.entries.remove(key);
+        None
+    }
+
+    fn insert(&mut self, key: &str, name: &str) -> Result<(), String> {
+        self.entries.insert(key.to_string(), name.to_string());
+        if self.lines >= self.entries.len() * 2 + 64 {
+            return self.compact();
+        }
+
+        let line = serde_json::to_string(&(key, name))
+            .map_err(|e| format!("序列化截图索引失败: {}", e))?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(SCREENSHOT_INDEX_FILE))
+            .map_err(|e| format!("保存截图索引失败: {}", e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("保存截图索引失败: {}", e))?;
+        self.lines += 1;
+        Ok(())
+    }
+
+    /// 去掉文件已不存在的条目，重写索引文件
+    fn compact(&mut self) -> Result<(), String> {
+        let dir = &self.dir;
+        self.entries.retain(|_, name| dir.join(name.as_str()).exists());
+
+        let mut content = String::new();
+        for entry in &self.entries {
+            let line = serde_json::to_string(&entry)
+                .map_err(|e| format!("序列化截图索引失败: {}", e))?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        let temp = dir.join(format!("{}.tmp", SCREENSHOT_INDEX_FILE));
+        fs::write(&temp, content)
+            .and_then(|_| fs::rename(&temp, dir.join(SCREENSHOT_INDEX_FILE)))
+            .map_err(|e| format!("保存截图索引失败: {}", e))?;
+        self.lines = self.entries.len();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.lines = 0;
+        let _ = fs::remove_file(self.dir.join(SCREENSHOT_INDEX_FILE));
+        let _ = fs::remove_file(self.dir.join(LEGACY_SCREENSHOT_INDEX_FILE));
+    }
+}
+
+/// 持有索引锁执行操作；只用于查找和写入条目，不要在其中编码图片
+fn with_screenshot_index<T>(dir: &Path, action: impl FnOnce(&mut ScreenshotIndex) -> T) -> T {
+    let mut guard = SCREENSHOT_INDEX.lock();
+    if guard.as_ref().map(|index| index.dir != dir).unwrap_or(false) {
+        *guard = None;
+    }
+    action(guard.get_or_insert_with(|| ScreenshotIndex::load(dir)))
+}
+
+/// 截图对应的缩略图路径（统一为 JPEG）
+fn thumbnail_path(dir: &Path, name: &str) -> PathBuf {
+    let stem = Path::new(name)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(name);
+    dir.join(THUMBNAIL_DIR).join(format!("{}.jpg", stem))
+}
+
+/// 缩略图不存在时由原图生成，返回缩略图路径
+fn ensure_thumbnail(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let target = thumbnail_path(dir, name);
+    if target.exists() {
+        return Ok(target);
+    }
+
+    let image = image::open(dir.join(name)).map_err(|e| format!("读取截图失败: {}", e))?;
+    fs::create_dir_all(dir.join(THUMBNAIL_DIR))
+        .map_err(|e| format!("创建缩略图目录失败: {}", e))?;
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .save_with_format(&target, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("保存缩略图失败: {}", e))?;
+    Ok(target)
+}
+
+/// 删除截图及其缩略图
+fn remove_screenshot_files(dir: &Path, name: &str) {
+    let _ = fs::remove_file(dir.join(name));
+    let _ = fs::remove_file(thumbnail_path(dir, name));
+}
+
+/// 按扩展名判断截图的 MIME 类型
+pub fn screenshot_mime(name: &str) -> &'static str {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        _ => "image/jpeg",
+    }
+}
+
+fn is_screenshot_file(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
+    matches!(ext.as_str(), "jpg" | "jpeg" | "png")
+}
+
 fn sanitize_log_prefix(prefix: &str) -> String {
     let mut clean = String::new();
     for ch in prefix.chars() {
@@ -722,28 +1690,94 @@ pub struct SearchQuery {
     pub time_range: TimeRange,
     pub keywords: Vec<String>,
     pub include_detail: bool,
+    /// 只保留这些应用的记录（归一化应用名，任一匹配即可）
+    pub apps: Vec<String>,
+    /// 只保留这些活动类型的记录
+    pub activity_types: Vec<String>,
+    /// 只保留涉及这些文件的记录
+    pub files: Vec<String>,
 }
 
 impl SearchQuery {
+    pub fn has_filters(&self) -> bool {
+        !self.apps.is_empty() || !self.activity_types.is_empty() || !self.files.is_empty()
+    }
+
+    /// 按应用、活动类型、文件过滤；每类条件内任一匹配即可，不同类条件需同时满足
+    pub fn matches_filters(&self, record: &SummaryRecord) -> bool {
+        let app = record.app_label().to_lowercase();
+        let file = record.file.to_lowercase();
+
+        (self.apps.is_empty() || self.apps.iter().any(|a| app.contains(&a.to_lowercase())))
+            && (self.activity_types.is_empty()
+                || self.activity_types.iter().any(|t| t.eq_ignore_ascii_case(&record.activity_type)))
+            && (self.files.is_empty()
+                || (!file.is_empty() && self.files.iter().any(|f| file.contains(&f.to_lowercase()))))
+    }
+
+    /// 聚合记录的过滤：应用、活动类型、文件列表中任一匹配即可
+    pub fn matches_aggregated(&self, agg: &AggregatedRecord) -> bool {
+        contains_any(&self.apps, &agg.apps)
+            && contains_any(&self.activity_types, &agg.activity_types)
+            && contains_any(&self.files, &agg.files)
+    }
+
+    /// 工作段的过滤：与聚合记录相同，活动类型只看停留最久的一种
+    pub fn matches_session(&self, session: &WorkSession) -> bool {
+        contains_any(&self.apps, &session.apps)
+            && contains_any(&self.activity_types, std::slice::from_ref(&session.activity_type))
+            && contains_any(&self.files, &session.files)
+    }
+
+    /// 关键词匹配：原文包含关键词，或分词后覆盖关键词的大部分词项（中文没有空格，措辞略有不同也能命中）
     pub fn matches_keywords(&self, record: &SummaryRecord) -> bool {
         if self.keywords.is_empty() {
             return true;
         }
 
-        let text = format!("{} {} {}",
+        let text = format!("{} {} {} {} {}",
             record.summary,
-            record.app,
-            format!("{} {}", record.detail, record.keywords.join(" "))
-        ).to_lowercase();
+            record.app_label(),
+            record.detail,
+            record.keywords.join(" "),
+            record.screen_text
+        );
+        let lower = text.to_lowercase();
+        if self.keywords.iter().any(|kw| lower.contains(&kw.to_lowercase())) {
+            return true;
+        }
 
-        self.keywords.iter().any(|kw| text.contains(&kw.to_lowercase()))
+        let terms = Tokenizer::terms(&text);
+        self.keywords.iter().any(|kw| Tokenizer::matches(&terms, kw))
     }
 }
 
+/// 没有过滤条件，或任一条件包含在任一取值中（不区分大小写）
+fn contains_any(filters: &[String], values: &[String]) -> bool {
+    filters.is_empty()
+        || filters.iter().any(|f| {
+            let f = f.to_lowercase();
+            values.iter().any(|v| v.to_lowercase().contains(&f))
+        })
+}
+
+/// 屏幕原文的一条命中
+#[derive(Debug, Clone, Serialize)]
+pub struct ScreenTextMatch {
+    pub timestamp: String,
+    pub app: String,
+    pub summary: String,
+    pub detail_ref: String,
+    /// 包含查找内容的那一行
+    pub line: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub records: Vec<SummaryRecord>,
     pub aggregated: Vec<AggregatedRecord>,
+    /// 检索范围内的工作段，由调用方按需补充
+    pub sessions: Vec<WorkSession>,
     pub source: String,
 }
 
@@ -753,15 +1787,51 @@ impl SearchResult {
         let mut context = String::new();
         let mut current_len = 0;
 
-        // 先添加聚合记录（概要）
+        // 先添加工作段（按任务划分的时间线）
+        if !self.sessions.is_empty() {
+            context.push_str("## 工作段\n\n");
+            for session in &self.sessions {
+                let mut line = format!(
+                    "- [{} {}] {}",
+                    session.start.get(..10).unwrap_or_default(),
+                    session.time_label(),
+                    session.title
+                );
+                if !session.app.is_empty() {
+                    line.push_str(&format!("（{}", session.app));
+                    if !session.project.is_empty() {
+                        line.push_str(&format!("，项目 {}", session.project));
+                    }
+                    line.push('）');
+                }
+                if session.issue_count > 0 {
+                    line.push_str(&format!("，遇到 {} 个问题", session.issue_count));
+                }
+                line.push('\n');
+                if current_len + line.len() > max_chars {
+                    break;
+                }
+                context.push_str(&line);
+                current_len += line.len();
+            }
+            context.push('\n');
+        }
+
+        // 再添加聚合记录（概要）
         if !self.aggregated.is_empty() {
             context.push_str("## 操作概要\n\n");
             for agg in &self.aggregated {
+                let activities = if agg.activity_types.is_empty() {
+                    String::new()
+                } else {
+                    format!("（{}）", agg.activity_types.join("、"))
+                };
                 let line = format!(
-                    "- [{} ~ {}] {}\n",
+                    "- [{} ~ {}] {}{}\n",
                     &agg.start_time[11..16],
                     &agg.end_time[11..16],
-                    agg.summary
+                    agg.summary,
+                    activities
                 );
                 if current_len + line.len() > max_chars {
                     break;
@@ -788,11 +1858,7 @@ impl SearchResult {
             let mut truncated = false;
 
             for record in self.records.iter().rev() {
-                let line = format!(
-                    "- [{}] {}\n",
-                    &record.timestamp[11..19],
-                    record.summary
-                );
+                let line = format!("- [{}] {}\n", record.time_label(), record.summary);
                 if current_len + line.len() > max_chars {
                     truncated = true;
                     break;
@@ -41,12 +41,17 @@ async function setupAlertListener() {
       error_type?: string
       message: string
       suggestion?: string
+      issue?: { file?: string; line?: number; column?: number } | null
     }>('assistant-alert', (event) => {
       const alert = event.payload
       const alertType = alert.issue_type || alert.error_type || 'unknown'
       let content = `⚠️ **检测到问题**\n\n`
       content += `**类型**: ${alertType}\n`
       content += `**信息**: ${alert.message}\n`
+      if (alert.issue?.file) {
+        const { file, line, column } = alert.issue
+        content += `**位置**: ${file}${line ? `:${line}` : ''}${line && column ? `:${column}` : ''}\n`
+      }
       if (alert.suggestion) {
         content += `\n**建议**: ${alert.suggestion}`
       }
@@ -74,6 +79,7 @@ async function pollAlerts() {
       issue_type: string
       message: string
       suggestion?: string
+      issue?: { file?: string; line?: number; column?: number } | null
     }>>('get_recent_alerts', { since: lastAlertTimestamp })
 
     if (alerts && alerts.length > 0) {
@@ -82,6 +88,10 @@ async function pollAlerts() {
         let content = `⚠️ **检测到问题**\n\n`
         content += `**类型**: ${alertType}\n`
         content += `**信息**: ${alert.message}\n`
+        if (alert.issue?.file) {
+          const { file, line, column } = alert.issue
+          content += `**位置**: ${file}${line ? `:${line}` : ''}${line && column ? `:${column}` : ''}\n`
+        }
         if (alert.suggestion) {
           content += `\n**建议**: ${alert.suggestion}`
         }
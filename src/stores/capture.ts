@@ -1,15 +1,40 @@
 import { defineStore } from 'pinia'
 import { ref, onMounted, onUnmounted } from 'vue'
 
+export interface CaptureStatus {
+  is_capturing: boolean
+  record_count: number
+  last_capture_time: string | null
+  is_paused: boolean
+  pause_reason: 'paused' | 'off_schedule' | null
+  resume_at: string | null
+  last_analysis_time: string | null
+  skipped_count: number
+  failed_count: number
+  avg_latency_ms: number | null
+  p95_latency_ms: number | null
+  current_interval_ms: number
+  last_error: string | null
+  last_error_time: string | null
+}
+
+// 后端运行时会推送 capture-status 事件，轮询只作为兜底
+const STATUS_POLL_INTERVAL_MS = 30000
+
 export const useCaptureStore = defineStore('capture', () => {
   const isCapturing = ref(false)
   const recordCount = ref(0)
   const lastCaptureTime = ref<string | null>(null)
+  const isPaused = ref(false)
+  const pauseReason = ref<'paused' | 'off_schedule' | null>(null)
+  const resumeAt = ref<string | null>(null)
+  const status = ref<CaptureStatus | null>(null)
   const desiredCapturing = ref(false)
   const autoRestarting = ref(false)
   const lastEvent = ref<{ id: number; type: 'warning' | 'success' | 'error'; message: string } | null>(null)
 
   let statusInterval: number | null = null
+  let unlistenStatus: (() => void) | null = null
   let eventSeq = 0
   let lastAutoRestartAt = 0
 
@@ -42,22 +67,46 @@ export const useCaptureStore = defineStore('capture', () => {
     }
   }
 
+  async function pauseCapture(minutes?: number) {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core')
+      await invoke('pause_capture', { minutes: minutes ?? null })
+      await refreshStatus()
+    } catch (error) {
+      console.error('Failed to pause capture:', error)
+      throw error
+    }
+  }
+
+  async function resumeCapture() {
+    try {
+      const { invoke } = await import('@tauri-apps/api/core')
+      await invoke('resume_capture')
+      await refreshStatus()
+    } catch (error) {
+      console.error('Failed to resume capture:', error)
+      throw error
+    }
+  }
+
+  async function applyStatus(next: CaptureStatus) {
+    status.value = next
+    isCapturing.value = next.is_capturing
+    recordCount.value = next.record_count
+    lastCaptureTime.value = next.last_capture_time
+    isPaused.value = next.is_paused
+    pauseReason.value = next.pause_reason
+    resumeAt.value = next.resume_at
+
+    if (desiredCapturing.value && !next.is_capturing) {
+      await attemptAutoRestart()
+    }
+  }
+
   async function refreshStatus() {
     try {
       const { invoke } = await import('@tauri-apps/api/core')
-      const status = await invoke<{
-        is_capturing: boolean
-        record_count: number
-        last_capture_time: string | null
-      }>('get_capture_status')
-
-      isCapturing.value = status.is_capturing
-      recordCount.value = status.record_count
-      lastCaptureTime.value = status.last_capture_time
-
-      if (desiredCapturing.value && !status.is_capturing) {
-        await attemptAutoRestart()
-      }
+      await applyStatus(await invoke<CaptureStatus>('get_capture_status'))
     } catch (error) {
       console.error('Failed to refresh status:', error)
     }
@@ -83,9 +132,18 @@ export const useCaptureStore = defineStore('capture', () => {
     }
   }
 
-  function startStatusPolling() {
+  async function startStatusPolling() {
     refreshStatus()
-    statusInterval = window.setInterval(refreshStatus, 5000)
+    statusInterval = window.setInterval(refreshStatus, STATUS_POLL_INTERVAL_MS)
+
+    try {
+      const { listen } = await import('@tauri-apps/api/event')
+      unlistenStatus = await listen<CaptureStatus>('capture-status', (event) => {
+        applyStatus(event.payload)
+      })
+    } catch (error) {
+      console.error('Failed to listen capture status:', error)
+    }
   }
 
   function stopStatusPolling() {
@@ -93,17 +151,27 @@ export const useCaptureStore = defineStore('capture', () => {
       clearInterval(statusInterval)
       statusInterval = null
     }
+    if (unlistenStatus) {
+      unlistenStatus()
+      unlistenStatus = null
+    }
   }
 
   return {
     isCapturing,
     recordCount,
     lastCaptureTime,
+    isPaused,
+    pauseReason,
+    resumeAt,
+    status,
     desiredCapturing,
     autoRestarting,
     lastEvent,
     startCapture,
     stopCapture,
+    pauseCapture,
+    resumeCapture,
     refreshStatus,
     startStatusPolling,
     stopStatusPolling,
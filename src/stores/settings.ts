@@ -1,6 +1,22 @@
 import { defineStore } from 'pinia'
 import { ref } from 'vue'
 
+export interface RedactionRegion {
+  display_id?: string | null
+  x: number
+  y: number
+  width: number
+  height: number
+  unit: 'px' | 'ratio'
+  style: 'black' | 'blur'
+}
+
+export interface CaptureWindow {
+  days: number[]
+  start: string
+  end: string
+}
+
 export interface AppConfig {
   model: {
     provider: 'api' | 'ollama'
@@ -25,12 +41,47 @@ export interface AppConfig {
     recent_detail_limit: number
     alert_confidence_threshold: number
     alert_cooldown_seconds: number
+    capture_mode: 'primary' | 'display' | 'each' | 'stitched'
+    display_id: number | null
+    upload_max_width: number
+    upload_max_height: number
+    save_quality: number | null
+    save_max_width: number
+    save_max_height: number
+    adaptive_interval: boolean
+    min_interval_ms: number
+    max_interval_ms: number
+    change_detection: 'simple' | 'tiles'
+    tile_columns: number
+    tile_rows: number
+    tile_bit_threshold: number
+    tile_change_ratio: number
+    privacy_apps: string[]
+    privacy_patterns: string[]
+    privacy_cooldown_seconds: number
+    redaction_regions: RedactionRegion[]
+    scrub_secrets: boolean
+    scrub_patterns: string[]
+    schedule: CaptureWindow[]
+    analysis_concurrency: number
+    analysis_queue_size: number
+    analysis_batch_size: number
+    ocr_engine: 'none' | 'tesseract'
+    ocr_languages: string
+    ocr_data_path: string
+    ocr_max_chars: number
   }
   storage: {
     retention_days: number
     max_screenshots: number
     max_context_chars: number
     auto_clear_on_start: boolean
+    merge_spans: boolean
+    span_similarity: number
+    span_max_gap_seconds: number
+    session_idle_seconds: number
+    session_topic_similarity: number
+    session_switch_seconds: number
   }
 }
 
@@ -59,12 +110,47 @@ export const useSettingsStore = defineStore('settings', () => {
       recent_detail_limit: 3,
       alert_confidence_threshold: 0.7,
       alert_cooldown_seconds: 120,
+      capture_mode: 'primary',
+      display_id: null,
+      upload_max_width: 1920,
+      upload_max_height: 1080,
+      save_quality: null,
+      save_max_width: 0,
+      save_max_height: 0,
+      adaptive_interval: true,
+      min_interval_ms: 1000,
+      max_interval_ms: 15000,
+      change_detection: 'tiles',
+      tile_columns: 16,
+      tile_rows: 9,
+      tile_bit_threshold: 4,
+      tile_change_ratio: 0,
+      privacy_apps: [],
+      privacy_patterns: [],
+      privacy_cooldown_seconds: 300,
+      redaction_regions: [],
+      scrub_secrets: true,
+      scrub_patterns: [],
+      schedule: [],
+      analysis_concurrency: 1,
+      analysis_queue_size: 2,
+      analysis_batch_size: 1,
+      ocr_engine: 'none',
+      ocr_languages: 'chi_sim+eng',
+      ocr_data_path: '',
+      ocr_max_chars: 4000,
     },
     storage: {
       retention_days: 7,
       max_screenshots: 10000,
       max_context_chars: 10000,
       auto_clear_on_start: false,
+      merge_spans: true,
+      span_similarity: 0.6,
+      span_max_gap_seconds: 180,
+      session_idle_seconds: 900,
+      session_topic_similarity: 0.1,
+      session_switch_seconds: 120,
     },
   })
 